@@ -1,13 +1,17 @@
 use super::arrangement_config::ArrangementConfig;
 use crate::loc::Loc;
+use crate::metric::Metric;
 use crate::ntree::DataPoint;
 use crate::ntree::NTree;
+use crate::shape::{Ellipsoid, Shape};
 use crate::LightArrangementError;
 
 /// Manages the mapping from light index to location in N-dimensional space
 pub struct Arrangement<const N: usize> {
     ntree: NTree<usize, N>,
     number_lights: usize,
+    strip_length: usize,
+    metric: Metric<N>,
 }
 
 impl<const N: usize> Arrangement<N> {
@@ -28,15 +32,28 @@ impl<const N: usize> Arrangement<N> {
         return Ok(Arrangement {
             ntree,
             number_lights,
+            strip_length: config.strip_length(),
+            metric: Metric::Euclidean,
         });
     }
 
+    /// Measures distances for `get_closest` and `get_within_radius` with `metric`
+    pub fn set_metric(&mut self, metric: Metric<N>) {
+        self.metric = metric;
+    }
+
+    pub fn metric(&self) -> &Metric<N> {
+        &self.metric
+    }
+
     pub fn get_closest(
         &self,
         loc: &Loc<N>,
         max_search_distance: f64,
     ) -> Option<&DataPoint<usize, N>> {
-        let res = self.ntree.find_closest(&loc.coords, max_search_distance);
+        let res = self
+            .ntree
+            .find_closest(&loc.coords, max_search_distance, &self.metric);
         if let Ok(opt_datapoint) = res {
             return opt_datapoint;
         } else {
@@ -49,7 +66,8 @@ impl<const N: usize> Arrangement<N> {
         loc: &Loc<N>,
         max_search_distance: f64,
     ) -> Vec<&DataPoint<usize, N>> {
-        self.ntree.find_in_radius(&loc.coords, max_search_distance)
+        self.ntree
+            .find_in_radius(&loc.coords, max_search_distance, &self.metric)
     }
 
     pub fn get_within_bounding_box(
@@ -61,9 +79,37 @@ impl<const N: usize> Arrangement<N> {
             .find_in_box(&lower_corner.coords, &upper_corner.coords)
     }
 
+    /// Returns every light inside `ellipsoid`
+    pub fn get_within_ellipsoid(&self, ellipsoid: &Ellipsoid<N>) -> Vec<&DataPoint<usize, N>> {
+        self.ntree.find_in_ellipsoid(ellipsoid)
+    }
+
+    /// Returns every light inside `shape`, checking only those in its bounding box
+    pub fn get_within_shape<S: Shape<N> + ?Sized>(&self, shape: &S) -> Vec<&DataPoint<usize, N>> {
+        let (lower, upper) = shape.bounding_box();
+        self.get_within_bounding_box(
+            &lower.max(&Loc::cartesian([0.0; N])),
+            &upper.min(&Loc::cartesian([1.0; N])),
+        )
+        .into_iter()
+        .filter(|pt| shape.contains(&Loc::cartesian(pt.point)))
+        .collect()
+    }
+
+    /// Returns every light in the arrangement
+    pub fn get_all(&self) -> Vec<&DataPoint<usize, N>> {
+        self.ntree.find_in_box(&[0.0; N], &[1.0; N])
+    }
+
     pub fn number_lights(&self) -> usize {
         self.number_lights
     }
+
+    /// One more than the highest light index, so the length of a buffer holding every light even
+    /// when indices are skipped
+    pub fn strip_length(&self) -> usize {
+        self.strip_length
+    }
 }
 
 #[cfg(test)]
@@ -72,6 +118,8 @@ mod test {
 
     use super::*;
     use crate::loc::Loc;
+    use crate::shape::{Capsule, ConvexHull, Plane};
+    use crate::transform::Transform;
 
     #[test]
     fn get_closest() {
@@ -231,6 +279,143 @@ mod test {
         return Ok(());
     }
 
+    #[test]
+    fn get_all() -> Result<(), Box<dyn Error>> {
+        let arr = Arrangement::new(&ArrangementConfig {
+            light_locations: vec![
+                ([0.0, 0.0], 1),
+                ([1.0, 1.0], 2),
+                ([0.5, 0.5], 3),
+                ([0.0, 1.0], 4),
+            ],
+            number_children_for_division: 1,
+        })?;
+        let mut res = arr
+            .get_all()
+            .iter()
+            .map(|pt| pt.data)
+            .collect::<Vec<usize>>();
+        res.sort();
+        assert_eq!(res, vec![1, 2, 3, 4]);
+        return Ok(());
+    }
+
+    #[test]
+    fn get_with_metric() -> Result<(), Box<dyn Error>> {
+        let mut arr = Arrangement::new(&ArrangementConfig {
+            light_locations: vec![([0.5, 0.5], 1), ([0.7, 0.7], 2), ([0.5, 0.8], 3)],
+            number_children_for_division: 1,
+        })?;
+        arr.set_metric(Metric::Chebyshev);
+        let mut res = arr
+            .get_within_radius(&Loc::cartesian([0.5, 0.5]), 0.25)
+            .iter()
+            .map(|pt| pt.data)
+            .collect::<Vec<usize>>();
+        res.sort();
+        assert_eq!(res, vec![1, 2]);
+        assert_eq!(
+            arr.get_closest(&Loc::cartesian([0.6, 0.8]), 0.3)
+                .unwrap()
+                .data,
+            3
+        );
+
+        assert_eq!(
+            arr.get_closest(&Loc::cartesian([0.62, 0.77]), 1.0)
+                .unwrap()
+                .data,
+            2
+        );
+        // vertical distance counts 8 times as much as horizontal
+        arr.set_metric(Metric::WeightedEuclidean([0.5, 4.0]));
+        assert_eq!(
+            arr.get_closest(&Loc::cartesian([0.62, 0.77]), 1.0)
+                .unwrap()
+                .data,
+            3
+        );
+        return Ok(());
+    }
+
+    #[test]
+    fn get_within_ellipsoid() -> Result<(), Box<dyn Error>> {
+        let arr = Arrangement::new(&ArrangementConfig {
+            light_locations: vec![
+                ([0.5, 0.5, 0.5], 1),
+                ([0.5, 0.5, 0.8], 2),
+                ([0.8, 0.5, 0.5], 3),
+                ([0.5, 0.6, 0.5], 4),
+            ],
+            number_children_for_division: 1,
+        })?;
+        let get = |ellipsoid: &Ellipsoid<3>| {
+            let mut res = arr
+                .get_within_ellipsoid(ellipsoid)
+                .iter()
+                .map(|pt| pt.data)
+                .collect::<Vec<usize>>();
+            res.sort();
+            res
+        };
+        // a tall raindrop
+        let drop = Ellipsoid::new(Loc::cartesian([0.5, 0.5, 0.6]), [0.05, 0.05, 0.3]);
+        assert_eq!(get(&drop), vec![1, 2]);
+        // lying on its side
+        let drop = Ellipsoid::new(Loc::cartesian([0.6, 0.5, 0.5]), [0.05, 0.05, 0.3])
+            .with_rotation(Transform::rotation(2, 0, std::f64::consts::PI / 2.0));
+        assert_eq!(get(&drop), vec![1, 3]);
+        let glow = Ellipsoid::new(Loc::cartesian([0.5, 0.5, 0.5]), [0.4, 0.15, 0.1]);
+        assert_eq!(get(&glow), vec![1, 3, 4]);
+        return Ok(());
+    }
+
+    #[test]
+    fn get_within_shape() -> Result<(), Box<dyn Error>> {
+        let arr = Arrangement::new(&ArrangementConfig {
+            light_locations: vec![
+                ([0.5, 0.5], 1),
+                ([0.7, 0.5], 2),
+                ([0.5, 0.9], 3),
+                ([0.1, 0.1], 4),
+            ],
+            number_children_for_division: 1,
+        })?;
+        let capsule = Capsule::new(Loc::cartesian([0.5, 0.5]), Loc::cartesian([0.5, 1.0]), 0.1);
+        let mut res = arr
+            .get_within_shape(&capsule)
+            .iter()
+            .map(|pt| pt.data)
+            .collect::<Vec<usize>>();
+        res.sort();
+        assert_eq!(res, vec![1, 3]);
+
+        // shapes that go on forever are cut down to the arrangement
+        let half = Plane::new(Loc::cartesian([1.0, 1.0]), 1.0);
+        let shape: &dyn Shape<2> = &half;
+        let mut res = arr
+            .get_within_shape(shape)
+            .iter()
+            .map(|pt| pt.data)
+            .collect::<Vec<usize>>();
+        res.sort();
+        assert_eq!(res, vec![1, 4]);
+
+        let triangle = ConvexHull::from_vertices(&[
+            Loc::cartesian([0.4, 0.4]),
+            Loc::cartesian([0.8, 0.4]),
+            Loc::cartesian([0.4, 1.0]),
+        ])?;
+        let mut res = arr
+            .get_within_shape(&triangle)
+            .iter()
+            .map(|pt| pt.data)
+            .collect::<Vec<usize>>();
+        res.sort();
+        assert_eq!(res, vec![1, 2]);
+        return Ok(());
+    }
+
     #[test]
     fn number_lights() -> Result<(), Box<dyn Error>> {
         let arr = Arrangement::new(&ArrangementConfig {
@@ -2,7 +2,7 @@ use csv::StringRecord;
 use std::fs::File;
 use std::string::String;
 
-use crate::LightArrangementError;
+use crate::{LightArrangementError, Transform};
 
 pub struct ArrangementConfig<const N: usize> {
     pub light_locations: Vec<([f64; N], usize)>,
@@ -45,6 +45,23 @@ impl<const N: usize> ArrangementConfig<N> {
             )));
         }
     }
+
+    /// Number of lights a strip needs to hold every index, one more than the highest index
+    pub fn strip_length(&self) -> usize {
+        self.light_locations
+            .iter()
+            .map(|(_, index)| index + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Moves the location of every light by `transform`
+    /// Locations still need to be within 0..1 on all dimensions to create an `Arrangement`
+    pub fn apply_transform(&mut self, transform: &Transform<N>) {
+        for (loc, _) in self.light_locations.iter_mut() {
+            *loc = transform.apply_point(loc);
+        }
+    }
 }
 
 fn parse_record<const N: usize>(
@@ -107,4 +124,31 @@ mod test {
 
         return Ok(());
     }
+
+    #[test]
+    fn apply_transform() {
+        let mut config = ArrangementConfig {
+            light_locations: vec![([0.2, 0.4], 0), ([0.5, 0.5], 1)],
+            number_children_for_division: 1,
+        };
+        config.apply_transform(&Transform::scale(0.5).then(&Transform::translation([0.1, 0.0])));
+        let approx = |a: [f64; 2], b: [f64; 2]| (a[0] - b[0]).abs() + (a[1] - b[1]).abs() < 0.0001;
+        assert!(approx(config.light_locations[0].0, [0.2, 0.2]));
+        assert!(approx(config.light_locations[1].0, [0.35, 0.25]));
+        assert_eq!(config.light_locations[1].1, 1);
+    }
+
+    #[test]
+    fn strip_length() {
+        let config = ArrangementConfig {
+            light_locations: vec![([0.1], 5), ([0.5], 0), ([0.9], 9)],
+            number_children_for_division: 1,
+        };
+        assert_eq!(config.strip_length(), 10);
+        let empty = ArrangementConfig::<1> {
+            light_locations: vec![],
+            number_children_for_division: 1,
+        };
+        assert_eq!(empty.strip_length(), 0);
+    }
 }
@@ -0,0 +1,256 @@
+use crate::{blend::BlendMode, color::Color, light_strip::LightStrip};
+
+/// How `LightArrangement::show` turns its frame buffer into colors for the strip
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FrameBufferConfig {
+    pub tone_map: ToneMap,
+    pub dither: Dither,
+}
+
+impl FrameBufferConfig {
+    pub fn new(tone_map: ToneMap, dither: Dither) -> Self {
+        FrameBufferConfig { tone_map, dither }
+    }
+}
+
+impl Default for FrameBufferConfig {
+    /// Clamps values above 1.0 and dithers over time
+    fn default() -> Self {
+        FrameBufferConfig::new(ToneMap::Clamp, Dither::Temporal)
+    }
+}
+
+/// How values above full brightness are brought back into the range the strip can show
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ToneMap {
+    /// Cut every channel off at full brightness
+    Clamp,
+    /// Compress bright values so that `white` maps to full brightness, leaving dim values
+    /// nearly untouched
+    Reinhard { white: f32 },
+    /// `1 - e^(-exposure * value)`, which never quite reaches full brightness
+    Exposure(f32),
+}
+
+impl ToneMap {
+    #[inline]
+    fn apply(&self, value: f32) -> f32 {
+        let value = value.max(0.0);
+        match self {
+            ToneMap::Clamp => value.min(1.0),
+            ToneMap::Reinhard { white } => {
+                let mapped = value * (1.0 + value / (white * white)) / (1.0 + value);
+                mapped.min(1.0)
+            }
+            ToneMap::Exposure(exposure) => 1.0 - (-exposure * value).exp(),
+        }
+    }
+}
+
+/// How the frame buffer is rounded to 8 bit colors for the strip
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Dither {
+    /// Round to the nearest value
+    None,
+    /// Offset rounding by a fixed pattern across neighboring light indices, so a value between two
+    /// steps shows as a mix of both
+    Ordered,
+    /// Carry the rounding error of each light into the next frame, so a value between two steps
+    /// averages out over time. Smoothest for slow fades that are shown every frame
+    Temporal,
+}
+
+/// Thresholds of an 8 entry Bayer pattern, centered on 0
+const BAYER: [f32; 8] = [
+    -0.4375, 0.0625, -0.1875, 0.3125, -0.3125, 0.1875, -0.0625, 0.4375,
+];
+
+/// Floating point copy of every light's color that effects draw into before it is sent to the
+/// strip. Keeps the precision that 8 bit colors lose to repeated dimming and blending, and allows
+/// values above 1.0 that are tone mapped back into range when shown
+pub(crate) struct FrameBuffer {
+    colors: Vec<[f32; 3]>,
+    errors: Vec<[f32; 3]>,
+    config: FrameBufferConfig,
+}
+
+impl FrameBuffer {
+    pub fn new(number_lights: usize, config: FrameBufferConfig) -> Self {
+        FrameBuffer {
+            colors: vec![[0.0; 3]; number_lights],
+            errors: vec![[0.0; 3]; number_lights],
+            config,
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, index: usize) -> [f32; 3] {
+        self.colors[index]
+    }
+
+    /// Returns the color at `index` clamped to what a `Color` can hold, without tone mapping
+    #[inline]
+    pub fn get_color(&self, index: usize) -> Color {
+        let [r, g, b] = self.colors[index];
+        Color::from_unit(r as f64, g as f64, b as f64)
+    }
+
+    #[inline]
+    pub fn set(&mut self, index: usize, color: [f32; 3]) {
+        self.colors[index] = color;
+    }
+
+    /// Blends `color` onto the color at `index` with `mode`, applying only `intensity` of the way
+    #[inline]
+    pub fn blend(&mut self, index: usize, color: &Color, intensity: f64, mode: BlendMode) {
+        let (r, g, b) = color.unit_components();
+        let [dr, dg, db] = self.colors[index];
+        let dst = [dr as f64, dg as f64, db as f64];
+        let [r, g, b] = mode.blend_components_with_intensity(dst, [r, g, b], intensity);
+        self.colors[index] = [r as f32, g as f32, b as f32];
+    }
+
+    pub fn fill(&mut self, color: [f32; 3]) {
+        self.colors.iter_mut().for_each(|c| *c = color);
+    }
+
+    /// Tone maps, dithers and writes every light to `light_strip`
+    pub fn write_to<T: LightStrip>(&mut self, light_strip: &mut T) {
+        for index in 0..self.colors.len() {
+            let mut out = [0; 3];
+            for (channel, byte) in out.iter_mut().enumerate() {
+                let value = self.config.tone_map.apply(self.colors[index][channel]) * 255.0;
+                *byte = match self.config.dither {
+                    Dither::None => value.round(),
+                    Dither::Ordered => (value + BAYER[index % BAYER.len()]).round(),
+                    Dither::Temporal => {
+                        let wanted = value + self.errors[index][channel];
+                        let shown = wanted.round().clamp(0.0, 255.0);
+                        self.errors[index][channel] = wanted - shown;
+                        shown
+                    }
+                }
+                .clamp(0.0, 255.0) as u8;
+            }
+            light_strip.set(index, &Color::rgb(out[0], out[1], out[2]));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{arrangement::ArrangementConfig, TestStrip, TestStripDisplayConfig};
+
+    fn make_strip(number_lights: usize) -> TestStrip {
+        let arrangement_config = ArrangementConfig {
+            light_locations: (0..number_lights)
+                .map(|i| ([i as f64 / number_lights as f64], i))
+                .collect(),
+            number_children_for_division: 1,
+        };
+        TestStrip::new(&arrangement_config, &TestStripDisplayConfig::default())
+    }
+
+    #[test]
+    fn tone_maps() {
+        assert_eq!(ToneMap::Clamp.apply(0.5), 0.5);
+        assert_eq!(ToneMap::Clamp.apply(3.0), 1.0);
+        assert_eq!(ToneMap::Clamp.apply(-1.0), 0.0);
+
+        let reinhard = ToneMap::Reinhard { white: 4.0 };
+        assert_eq!(reinhard.apply(0.0), 0.0);
+        assert!((reinhard.apply(4.0) - 1.0).abs() < 0.0001);
+        assert_eq!(reinhard.apply(10.0), 1.0);
+        assert!(reinhard.apply(1.0) < reinhard.apply(2.0));
+
+        let exposure = ToneMap::Exposure(1.0);
+        assert_eq!(exposure.apply(0.0), 0.0);
+        assert!(exposure.apply(1.0) < exposure.apply(4.0));
+        assert!(exposure.apply(100.0) <= 1.0);
+    }
+
+    #[test]
+    fn config() {
+        let config = FrameBufferConfig::default();
+        assert_eq!(
+            config,
+            FrameBufferConfig::new(ToneMap::Clamp, Dither::Temporal)
+        );
+        assert_ne!(
+            config,
+            FrameBufferConfig::new(ToneMap::Exposure(1.0), Dither::Temporal)
+        );
+    }
+
+    #[test]
+    fn keeps_precision() {
+        let mut frame_buffer = FrameBuffer::new(1, FrameBufferConfig::default());
+        frame_buffer.set(0, [1.0, 1.0, 1.0]);
+        let mut color = Color::rgb(255, 255, 255);
+        for _ in 0..8 {
+            frame_buffer.blend(0, &Color::rgb(0, 0, 0), 1.0, BlendMode::AlphaOver(0.5));
+            color = BlendMode::AlphaOver(0.5).blend(&color, &Color::rgb(0, 0, 0));
+        }
+        assert!((frame_buffer.get(0)[0] - 1.0 / 256.0).abs() < 0.000001);
+        assert_eq!(color, Color::rgb(1, 1, 1));
+    }
+
+    #[test]
+    fn blend_intensity() {
+        let mut frame_buffer = FrameBuffer::new(1, FrameBufferConfig::default());
+        frame_buffer.set(0, [0.5, 0.5, 0.0]);
+        let white = Color::rgb(255, 255, 255);
+        frame_buffer.blend(0, &white, 0.25, BlendMode::Multiply);
+        assert_eq!(frame_buffer.get(0), [0.5, 0.5, 0.0]);
+        frame_buffer.blend(0, &white, 0.5, BlendMode::Replace);
+        assert_eq!(frame_buffer.get(0), [0.75, 0.75, 0.5]);
+        frame_buffer.blend(0, &white, 0.0, BlendMode::Average);
+        assert_eq!(frame_buffer.get(0), [0.75, 0.75, 0.5]);
+    }
+
+    #[test]
+    fn no_dither() {
+        let mut strip = make_strip(2);
+        let mut frame_buffer =
+            FrameBuffer::new(2, FrameBufferConfig::new(ToneMap::Clamp, Dither::None));
+        frame_buffer.set(0, [0.5, 2.0, -1.0]);
+        frame_buffer.set(1, [1.0 / 255.0, 0.0, 0.0]);
+        frame_buffer.write_to(&mut strip);
+        assert_eq!(strip.get(0), Color::rgb(128, 255, 0));
+        assert_eq!(strip.get(1), Color::rgb(1, 0, 0));
+        assert_eq!(frame_buffer.get_color(0), Color::rgb(128, 255, 0));
+    }
+
+    #[test]
+    fn temporal_dither() {
+        let mut strip = make_strip(1);
+        let mut frame_buffer =
+            FrameBuffer::new(1, FrameBufferConfig::new(ToneMap::Clamp, Dither::Temporal));
+        // a quarter of the way between 10 and 11
+        frame_buffer.set(0, [10.25 / 255.0, 0.0, 1.0]);
+
+        let mut total = 0;
+        for _ in 0..100 {
+            frame_buffer.write_to(&mut strip);
+            let shown = strip.get(0);
+            assert!(shown.red == 10 || shown.red == 11);
+            assert_eq!(shown.blue, 255);
+            total += shown.red as u32;
+        }
+        assert_eq!(total, 1025);
+    }
+
+    #[test]
+    fn ordered_dither() {
+        let mut strip = make_strip(8);
+        let mut frame_buffer =
+            FrameBuffer::new(8, FrameBufferConfig::new(ToneMap::Clamp, Dither::Ordered));
+        frame_buffer.fill([10.5 / 255.0, 0.0, 0.0]);
+        frame_buffer.write_to(&mut strip);
+
+        let shown: Vec<u8> = (0..8).map(|i| strip.get(i).red).collect();
+        assert!(shown.iter().all(|v| *v == 10 || *v == 11));
+        assert_eq!(shown.iter().map(|v| *v as u32).sum::<u32>(), 84);
+    }
+}
@@ -1,12 +1,32 @@
+use std::{panic, thread};
+
 use super::arrangement::Arrangement;
 use super::arrangement_config::ArrangementConfig;
+use super::frame_buffer::{FrameBuffer, FrameBufferConfig};
+use super::topology::Topology;
 use crate::LightArrangementError;
-use crate::{color::Color, light_strip::LightStrip, loc::Loc, math::distance};
+use crate::{
+    blend::BlendMode,
+    color::{Color, ColorA, ColorW, WhiteExtraction},
+    falloff::Falloff,
+    gradient::Gradient,
+    light_strip::LightStrip,
+    loc::Loc,
+    math::slab_in_unit_box,
+    metric::Metric,
+    path::Path,
+    shape::{Capsule, Ellipsoid, Plane, Shape},
+    transform::Transform,
+};
 
 /// Uses Arrangement and LightStrip to assign to lights based on lcation in N dimensional space
 pub struct LightArrangement<T: LightStrip, const N: usize> {
     arrangement: Arrangement<N>,
     light_strip: T,
+    frame_buffer: Option<FrameBuffer>,
+    topology: Topology<N>,
+    /// Transform from the frame effects are drawn in to the arrangement, and its inverse
+    frame: Option<(Transform<N>, Transform<N>)>,
 }
 
 impl<T: LightStrip, const N: usize> LightArrangement<T, N> {
@@ -16,38 +36,98 @@ impl<T: LightStrip, const N: usize> LightArrangement<T, N> {
     ) -> Result<Self, LightArrangementError> {
         Ok(LightArrangement {
             arrangement: Arrangement::new(&arrangement_config)?,
+            topology: Topology::new(&arrangement_config),
             light_strip,
+            frame_buffer: None,
+            frame: None,
         })
     }
 
-    pub fn get_closest(&self, loc: &Loc<N>, max_search_distance: f64) -> Option<Color> {
-        let datapoint = self.arrangement.get_closest(loc, max_search_distance);
-        if let Some(datapoint) = datapoint {
-            return Some(self.light_strip.get(datapoint.data));
-        } else {
-            return None;
+    /// Starts drawing into a floating point frame buffer instead of straight to the strip
+    /// Dimming and blending no longer round to 8 bits every time, and lights can be brighter than
+    /// full brightness. `show` tone maps and dithers the buffer onto the strip as set in `config`
+    pub fn enable_frame_buffer(&mut self, config: FrameBufferConfig) {
+        let strip_length = self.arrangement.strip_length();
+        let mut frame_buffer = FrameBuffer::new(strip_length, config);
+        for index in 0..strip_length {
+            let (r, g, b) = self.light_strip.get(index).float_components();
+            frame_buffer.set(index, [r, g, b]);
         }
+        self.frame_buffer = Some(frame_buffer);
     }
 
-    pub fn set_closest(&mut self, loc: &Loc<N>, max_set_distance: f64, color: &Color) {
-        let datapoint = self.arrangement.get_closest(loc, max_set_distance);
-        if let Some(datapoint) = datapoint {
-            self.light_strip.set(datapoint.data, color);
-        }
+    /// Goes back to drawing straight to the strip. The strip keeps what was last shown
+    pub fn disable_frame_buffer(&mut self) {
+        self.frame_buffer = None;
     }
 
-    pub fn set_decreasing_intensity(&mut self, loc: &Loc<N>, set_distance: f64, color: &Color) {
-        let datapoints = self.arrangement.get_within_radius(loc, set_distance);
-        for pt in datapoints.iter() {
-            let distance = distance(&pt.point, &loc.coords);
+    /// Runs every query after this in the frame moved by `transform`, so a location `loc`
+    /// passed to a setter refers to the point `transform.apply(loc)` of the arrangement, and radii
+    /// and boxes are measured in that frame too. Rotating the frame rotates every effect drawn
+    /// Returns an error and keeps the current frame if `transform` cannot be undone
+    pub fn set_transform(&mut self, transform: Transform<N>) -> Result<(), LightArrangementError> {
+        let inverse = transform.inverse().ok_or_else(|| {
+            LightArrangementError::new("Transform flattens space and has no inverse".to_string())
+        })?;
+        self.frame = Some((transform, inverse));
+        Ok(())
+    }
+
+    /// Goes back to querying the arrangement in its own coordinates
+    pub fn clear_transform(&mut self) {
+        self.frame = None;
+    }
 
-            let mut color = color.clone();
-            color.dim(1.0 - (distance / set_distance));
+    /// The transform queries are run through, which is the identity if none was set
+    pub fn transform(&self) -> Transform<N> {
+        self.frame
+            .map(|(transform, _)| transform)
+            .unwrap_or_default()
+    }
+
+    /// Measures distances with `metric` for every query and fade after this, so for example
+    /// `Metric::Chebyshev` makes `set_all_in_radius` fill squares and `set_decreasing_intensity`
+    /// fade out in squares
+    pub fn set_metric(&mut self, metric: Metric<N>) {
+        self.arrangement.set_metric(metric);
+    }
+
+    pub fn metric(&self) -> &Metric<N> {
+        self.arrangement.metric()
+    }
+
+    pub fn get_closest(&self, loc: &Loc<N>, max_search_distance: f64) -> Option<Color> {
+        self.closest_index(loc, max_search_distance)
+            .map(|index| self.get_color(index))
+    }
+
+    pub fn set_closest(&mut self, loc: &Loc<N>, max_set_distance: f64, color: &Color) {
+        self.set_closest_blend(loc, max_set_distance, color, BlendMode::Replace);
+    }
 
-            self.light_strip.set(pt.data, &color);
+    /// Blends `color` onto the light closest to `loc` that is within `max_set_distance`
+    pub fn set_closest_blend(
+        &mut self,
+        loc: &Loc<N>,
+        max_set_distance: f64,
+        color: &Color,
+        mode: BlendMode,
+    ) {
+        if let Some(index) = self.closest_index(loc, max_set_distance) {
+            self.paint(index, color, 1.0, mode);
         }
     }
 
+    pub fn set_decreasing_intensity(&mut self, loc: &Loc<N>, set_distance: f64, color: &Color) {
+        self.set_decreasing_intensity_blend(
+            loc,
+            set_distance,
+            color,
+            &Falloff::Linear,
+            BlendMode::Replace,
+        );
+    }
+
     /// Sets lights at `loc` with a decreasing intensity outward
     /// When setting, will merge `color` and the color of the light using a simple max method
     pub fn set_decreasing_intensity_merge(
@@ -56,60 +136,933 @@ impl<T: LightStrip, const N: usize> LightArrangement<T, N> {
         set_distance: f64,
         color: &Color,
     ) {
-        let datapoints = self.arrangement.get_within_radius(loc, set_distance);
-        for pt in datapoints.iter() {
-            let distance = distance(&pt.point, &loc.coords);
-
-            let mut color = color.clone();
-            color.dim(1.0 - (distance / set_distance));
+        self.set_decreasing_intensity_blend(
+            loc,
+            set_distance,
+            color,
+            &Falloff::Linear,
+            BlendMode::Max,
+        );
+    }
 
-            let cur_color = self.light_strip.get(pt.data);
-            color.merge(cur_color);
-            self.light_strip.set(pt.data, &color);
+    /// Sets lights at `loc` with an intensity decreasing outward along `falloff`, blending with
+    /// the current color of each light using `mode`
+    pub fn set_decreasing_intensity_blend(
+        &mut self,
+        loc: &Loc<N>,
+        set_distance: f64,
+        color: &Color,
+        falloff: &Falloff,
+        mode: BlendMode,
+    ) {
+        for (index, distance) in self.distances_within_radius(loc, set_distance) {
+            let intensity = falloff.intensity(distance / set_distance);
+            self.paint(index, color, intensity, mode);
         }
     }
 
     pub fn set_all_in_box(&mut self, lower_corner: &Loc<N>, upper_corner: &Loc<N>, color: &Color) {
-        for pt in self
-            .arrangement
-            .get_within_bounding_box(lower_corner, upper_corner)
-        {
-            self.light_strip.set(pt.data, color);
+        self.set_all_in_box_blend(lower_corner, upper_corner, color, BlendMode::Replace);
+    }
+
+    /// Blends `color` onto every light in the box between `lower_corner` and `upper_corner`
+    pub fn set_all_in_box_blend(
+        &mut self,
+        lower_corner: &Loc<N>,
+        upper_corner: &Loc<N>,
+        color: &Color,
+        mode: BlendMode,
+    ) {
+        for (index, _) in self.lights_in_box(lower_corner, upper_corner) {
+            self.paint(index, color, 1.0, mode);
         }
     }
 
     pub fn set_all_in_radius(&mut self, center: &Loc<N>, radius: f64, color: &Color) {
-        for pt in self.arrangement.get_within_radius(center, radius) {
-            self.light_strip.set(pt.data, color);
+        self.set_all_in_radius_blend(center, radius, color, BlendMode::Replace);
+    }
+
+    /// Blends `color` onto every light within `radius` of `center`
+    pub fn set_all_in_radius_blend(
+        &mut self,
+        center: &Loc<N>,
+        radius: f64,
+        color: &Color,
+        mode: BlendMode,
+    ) {
+        for (index, _) in self.distances_within_radius(center, radius) {
+            self.paint(index, color, 1.0, mode);
+        }
+    }
+
+    /// Sets every light inside `ellipsoid`
+    pub fn set_all_in_ellipsoid(&mut self, ellipsoid: &Ellipsoid<N>, color: &Color) {
+        self.set_all_in_ellipsoid_blend(ellipsoid, color, BlendMode::Replace);
+    }
+
+    /// Blends `color` onto every light inside `ellipsoid` using `mode`
+    pub fn set_all_in_ellipsoid_blend(
+        &mut self,
+        ellipsoid: &Ellipsoid<N>,
+        color: &Color,
+        mode: BlendMode,
+    ) {
+        for (index, _) in self.lights_in_ellipsoid(ellipsoid) {
+            self.paint(index, color, 1.0, mode);
         }
     }
 
+    /// Sets lights in `ellipsoid` with an intensity decreasing from its center out to nothing at
+    /// its surface
+    pub fn set_ellipsoid_decreasing_intensity(&mut self, ellipsoid: &Ellipsoid<N>, color: &Color) {
+        self.set_ellipsoid_decreasing_intensity_blend(
+            ellipsoid,
+            color,
+            &Falloff::Linear,
+            BlendMode::Replace,
+        );
+    }
+
+    /// Sets lights in `ellipsoid` with an intensity decreasing along `falloff` from its center out
+    /// to nothing at its surface, blending with the current color of each light using `mode`
+    pub fn set_ellipsoid_decreasing_intensity_blend(
+        &mut self,
+        ellipsoid: &Ellipsoid<N>,
+        color: &Color,
+        falloff: &Falloff,
+        mode: BlendMode,
+    ) {
+        for (index, distance) in self.lights_in_ellipsoid(ellipsoid) {
+            self.paint(index, color, falloff.intensity(distance), mode);
+        }
+    }
+
+    /// Paints `color` over the light closest to `loc` that is within `max_set_distance`
+    /// `opacity` in 0..1 is multiplied with the alpha of `color`
+    pub fn set_closest_rgba(
+        &mut self,
+        loc: &Loc<N>,
+        max_set_distance: f64,
+        color: &ColorA,
+        opacity: f64,
+    ) {
+        let mode = BlendMode::AlphaOver(color.opacity() * opacity);
+        self.set_closest_blend(loc, max_set_distance, &color.color(), mode);
+    }
+
+    /// Paints `color` over lights at `loc`, becoming more transparent outward along `falloff` until
+    /// it is fully transparent `set_distance` away
+    /// `opacity` in 0..1 is multiplied with the alpha of `color`
+    pub fn set_decreasing_intensity_rgba(
+        &mut self,
+        loc: &Loc<N>,
+        set_distance: f64,
+        color: &ColorA,
+        opacity: f64,
+        falloff: &Falloff,
+    ) {
+        let mode = BlendMode::AlphaOver(color.opacity() * opacity);
+        self.set_decreasing_intensity_blend(loc, set_distance, &color.color(), falloff, mode);
+    }
+
+    /// Paints `color` over every light in the box between `lower_corner` and `upper_corner`
+    /// `opacity` in 0..1 is multiplied with the alpha of `color`
+    pub fn set_all_in_box_rgba(
+        &mut self,
+        lower_corner: &Loc<N>,
+        upper_corner: &Loc<N>,
+        color: &ColorA,
+        opacity: f64,
+    ) {
+        let mode = BlendMode::AlphaOver(color.opacity() * opacity);
+        self.set_all_in_box_blend(lower_corner, upper_corner, &color.color(), mode);
+    }
+
+    /// Paints `color` over every light within `radius` of `center`
+    /// `opacity` in 0..1 is multiplied with the alpha of `color`
+    pub fn set_all_in_radius_rgba(
+        &mut self,
+        center: &Loc<N>,
+        radius: f64,
+        color: &ColorA,
+        opacity: f64,
+    ) {
+        let mode = BlendMode::AlphaOver(color.opacity() * opacity);
+        self.set_all_in_radius_blend(center, radius, &color.color(), mode);
+    }
+
+    /// Colors every light by projecting its position onto the line from `start` to `end`
+    /// Lights level with `start` get the color at 0 of `gradient`, and lights level with `end` get
+    /// the color at 1. Lights beyond either end are colored by the gradient's mode
+    pub fn set_gradient_linear(&mut self, start: &Loc<N>, end: &Loc<N>, gradient: &Gradient) {
+        let mut direction = [0.0; N];
+        for (i, d) in direction.iter_mut().enumerate() {
+            *d = end.coords[i] - start.coords[i];
+        }
+        let length_squared: f64 = direction.iter().map(|d| d * d).sum();
+        if length_squared == 0.0 {
+            return;
+        }
+
+        for (index, point) in self.all_lights() {
+            let mut projected = 0.0;
+            for (i, d) in direction.iter().enumerate() {
+                projected += (point[i] - start.coords[i]) * d;
+            }
+            let color = gradient.sample(projected / length_squared);
+            self.paint(index, &color, 1.0, BlendMode::Replace);
+        }
+    }
+
+    /// Colors every light by its distance from `center`
+    /// Lights at `center` get the color at 0 of `gradient`, and lights `radius` away get the color
+    /// at 1. Lights further than `radius` are colored by the gradient's mode, and a `radius` of 0 or
+    /// less leaves every light as it is
+    pub fn set_gradient_radial(&mut self, center: &Loc<N>, radius: f64, gradient: &Gradient) {
+        if radius <= 0.0 {
+            return;
+        }
+        let metric = *self.metric();
+        let distances: Vec<(usize, f64)> = self
+            .all_lights()
+            .into_iter()
+            .map(|(index, point)| (index, metric.distance(&point, &center.coords)))
+            .collect();
+        for (index, distance) in distances {
+            self.paint(
+                index,
+                &gradient.sample(distance / radius),
+                1.0,
+                BlendMode::Replace,
+            );
+        }
+    }
+
+    /// Colors every light by its index, so `gradient` is laid out along the wiring of the strip
+    /// Light 0 gets the color at 0 of `gradient`, and the light with the highest index gets the
+    /// color at 1
+    pub fn set_gradient_by_index(&mut self, gradient: &Gradient) {
+        let last = self.arrangement.strip_length().saturating_sub(1).max(1) as f64;
+        let indices: Vec<usize> = self
+            .arrangement
+            .get_all()
+            .iter()
+            .map(|pt| pt.data)
+            .collect();
+        for index in indices {
+            let color = gradient.sample(index as f64 / last);
+            self.paint(index, &color, 1.0, BlendMode::Replace);
+        }
+    }
+
+    /// Paints `color` along `path`, fading out to nothing `thickness` away from it
+    pub fn set_path(&mut self, path: &Path<N>, thickness: f64, color: &Color) {
+        self.set_path_blend(path, thickness, color, &Falloff::Linear, BlendMode::Replace);
+    }
+
+    /// Paints `color` along `path`, fading out along `falloff` to nothing `thickness` away from it,
+    /// blending with the current color of each light using `mode`
+    pub fn set_path_blend(
+        &mut self,
+        path: &Path<N>,
+        thickness: f64,
+        color: &Color,
+        falloff: &Falloff,
+        mode: BlendMode,
+    ) {
+        if thickness <= 0.0 {
+            return;
+        }
+        let (lower, upper) = path.bounding_box();
+        let margin = Loc::cartesian([thickness; N]);
+        let lights = self.lights_in_box(&(lower - margin), &(upper + margin));
+        for (index, point) in lights {
+            let distance = path.distance_to(&Loc::cartesian(point));
+            if distance < thickness {
+                self.paint(index, color, falloff.intensity(distance / thickness), mode);
+            }
+        }
+    }
+
+    /// Sets every light within `thickness` of the line segment between `from` and `to`
+    pub fn set_line(&mut self, from: &Loc<N>, to: &Loc<N>, thickness: f64, color: &Color) {
+        self.set_line_blend(from, to, thickness, color, BlendMode::Replace);
+    }
+
+    /// Blends `color` onto every light within `thickness` of the line segment between `from` and
+    /// `to` using `mode`
+    pub fn set_line_blend(
+        &mut self,
+        from: &Loc<N>,
+        to: &Loc<N>,
+        thickness: f64,
+        color: &Color,
+        mode: BlendMode,
+    ) {
+        let line = Capsule::new(*from, *to, thickness);
+        for (index, _) in self.distances_to_shape(&line, 0.0) {
+            self.paint(index, color, 1.0, mode);
+        }
+    }
+
+    /// Sets lights along the line segment between `from` and `to`, with a decreasing intensity
+    /// outward to nothing `thickness` away from it
+    pub fn set_line_decreasing_intensity(
+        &mut self,
+        from: &Loc<N>,
+        to: &Loc<N>,
+        thickness: f64,
+        color: &Color,
+    ) {
+        self.set_line_decreasing_intensity_blend(
+            from,
+            to,
+            thickness,
+            color,
+            &Falloff::Linear,
+            BlendMode::Replace,
+        );
+    }
+
+    /// Sets lights along the line segment between `from` and `to`, with an intensity decreasing
+    /// along `falloff` outward to nothing `thickness` away from it, blending with the current
+    /// color of each light using `mode`
+    pub fn set_line_decreasing_intensity_blend(
+        &mut self,
+        from: &Loc<N>,
+        to: &Loc<N>,
+        thickness: f64,
+        color: &Color,
+        falloff: &Falloff,
+        mode: BlendMode,
+    ) {
+        if thickness <= 0.0 {
+            return;
+        }
+        let line = Capsule::new(*from, *to, thickness);
+        for (index, distance) in self.distances_to_shape(&line, 0.0) {
+            // distances are to the surface of the capsule, `thickness` out from the segment
+            let distance = distance + thickness;
+            self.paint(index, color, falloff.intensity(distance / thickness), mode);
+        }
+    }
+
+    /// Paints `color` on every light on the side of `plane` facing away from its normal, fading out
+    /// to nothing `edge_softness` past the plane
+    /// Lights always lie in the unit box, so only the part of it on that side is checked
+    pub fn set_half_space(&mut self, plane: &Plane<N>, edge_softness: f64, color: &Color) {
+        self.set_half_space_blend(
+            plane,
+            edge_softness,
+            color,
+            &Falloff::Linear,
+            BlendMode::Replace,
+        );
+    }
+
+    /// Paints `color` on every light on the side of `plane` facing away from its normal, fading
+    /// out along `falloff` to nothing `edge_softness` past the plane, blending with the current
+    /// color of each light using `mode`
+    pub fn set_half_space_blend(
+        &mut self,
+        plane: &Plane<N>,
+        edge_softness: f64,
+        color: &Color,
+        falloff: &Falloff,
+        mode: BlendMode,
+    ) {
+        let edge_softness = edge_softness.max(0.0);
+        for (index, distance) in self.distances_to_plane(plane, f64::NEG_INFINITY, edge_softness) {
+            let intensity = edge_intensity(distance, edge_softness, falloff);
+            self.paint(index, color, intensity, mode);
+        }
+    }
+
+    /// Paints `color` on every light within `thickness / 2` of `plane`, fading out to nothing
+    /// `edge_softness` further out
+    /// Lights always lie in the unit box, so only the part of the slab inside it is checked
+    pub fn set_slab(
+        &mut self,
+        plane: &Plane<N>,
+        thickness: f64,
+        edge_softness: f64,
+        color: &Color,
+    ) {
+        self.set_slab_blend(
+            plane,
+            thickness,
+            edge_softness,
+            color,
+            &Falloff::Linear,
+            BlendMode::Replace,
+        );
+    }
+
+    /// Paints `color` on every light within `thickness / 2` of `plane`, fading out along `falloff`
+    /// to nothing `edge_softness` further out, blending with the current color of each light using
+    /// `mode`
+    pub fn set_slab_blend(
+        &mut self,
+        plane: &Plane<N>,
+        thickness: f64,
+        edge_softness: f64,
+        color: &Color,
+        falloff: &Falloff,
+        mode: BlendMode,
+    ) {
+        let edge_softness = edge_softness.max(0.0);
+        let reach = thickness.max(0.0) / 2.0 + edge_softness;
+        for (index, distance) in self.distances_to_plane(plane, -reach, reach) {
+            let distance = distance.abs() - thickness.max(0.0) / 2.0;
+            let intensity = edge_intensity(distance, edge_softness, falloff);
+            self.paint(index, color, intensity, mode);
+        }
+    }
+
+    /// Paints `color` on every light inside `shape`, fading out to nothing `edge_softness` outside
+    /// its surface. Only lights in the bounding box of the shape are checked
+    pub fn set_shape<S: Shape<N> + ?Sized>(
+        &mut self,
+        shape: &S,
+        color: &Color,
+        edge_softness: f64,
+    ) {
+        self.set_shape_blend(
+            shape,
+            color,
+            edge_softness,
+            &Falloff::Linear,
+            BlendMode::Replace,
+        );
+    }
+
+    /// Paints `color` on every light inside `shape`, fading out along `falloff` to nothing
+    /// `edge_softness` outside its surface, blending with the current color of each light using
+    /// `mode`
+    pub fn set_shape_blend<S: Shape<N> + ?Sized>(
+        &mut self,
+        shape: &S,
+        color: &Color,
+        edge_softness: f64,
+        falloff: &Falloff,
+        mode: BlendMode,
+    ) {
+        let edge_softness = edge_softness.max(0.0);
+        for (index, distance) in self.distances_to_shape(shape, edge_softness) {
+            let intensity = edge_intensity(distance, edge_softness, falloff);
+            self.paint(index, color, intensity, mode);
+        }
+    }
+
+    /// Breaks the wiring after each index in `breaks`, for strips made of separate runs, replacing
+    /// any breaks set before
+    pub fn set_strip_breaks(&mut self, breaks: &[usize]) {
+        self.topology.set_breaks(breaks);
+    }
+
+    /// How the lights are wired together, for measuring distances along the strip
+    pub fn topology(&self) -> &Topology<N> {
+        &self.topology
+    }
+
+    /// Paints `color` at `position` along the wire, fading out to nothing `width` away in either
+    /// direction. `position` is the distance along the wire from light 0, like
+    /// `Topology::arc_length`, so moving it steadily moves at a steady speed through space
+    pub fn set_along_strip(&mut self, position: f64, width: f64, color: &Color) {
+        self.set_along_strip_blend(position, width, color, &Falloff::Linear, BlendMode::Replace);
+    }
+
+    /// Paints `color` at `position` along the wire, fading out along `falloff` to nothing `width`
+    /// away in either direction, blending with the current color of each light using `mode`
+    pub fn set_along_strip_blend(
+        &mut self,
+        position: f64,
+        width: f64,
+        color: &Color,
+        falloff: &Falloff,
+        mode: BlendMode,
+    ) {
+        if width <= 0.0 {
+            return;
+        }
+        let lights: Vec<(usize, f64)> = self
+            .topology
+            .arc_lengths()
+            .map(|(index, length)| (index, (length - position).abs()))
+            .filter(|(_, distance)| *distance < width)
+            .collect();
+        for (index, distance) in lights {
+            self.paint(index, color, falloff.intensity(distance / width), mode);
+        }
+    }
+
+    /// Sets every light to the color `f` returns for its location and index
+    /// Locations are in the frame set by `set_transform`
+    pub fn set_by_fn<F: FnMut(&Loc<N>, usize) -> Color>(&mut self, f: F) {
+        let lights = self.all_lights();
+        self.set_lights_by_fn(lights, f);
+    }
+
+    /// Sets every light in the box between `lower_corner` and `upper_corner` to the color `f`
+    /// returns for its location and index
+    pub fn set_by_fn_in_box<F: FnMut(&Loc<N>, usize) -> Color>(
+        &mut self,
+        lower_corner: &Loc<N>,
+        upper_corner: &Loc<N>,
+        f: F,
+    ) {
+        let lights = self.lights_in_box(lower_corner, upper_corner);
+        self.set_lights_by_fn(lights, f);
+    }
+
+    /// Sets every light within `radius` of `center` to the color `f` returns for its location and
+    /// index
+    pub fn set_by_fn_in_radius<F: FnMut(&Loc<N>, usize) -> Color>(
+        &mut self,
+        center: &Loc<N>,
+        radius: f64,
+        f: F,
+    ) {
+        let lights = self
+            .lights_within_radius(center, radius)
+            .into_iter()
+            .map(|(index, point, _)| (index, point))
+            .collect();
+        self.set_lights_by_fn(lights, f);
+    }
+
+    /// Sets every light to the color `f` returns for its location and index, working out the
+    /// colors on every available thread. Worth it when `f` is slow or there are many lights
+    pub fn set_by_fn_parallel<F: Fn(&Loc<N>, usize) -> Color + Sync>(&mut self, f: F) {
+        let lights = self.all_lights();
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        let chunk_size = lights.len().div_ceil(threads).max(1);
+        let colors: Vec<Color> = thread::scope(|scope| {
+            let workers: Vec<_> = lights
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    let f = &f;
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|(index, point)| f(&Loc::cartesian(*point), *index))
+                            .collect::<Vec<Color>>()
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| {
+                    worker
+                        .join()
+                        .unwrap_or_else(|err| panic::resume_unwind(err))
+                })
+                .collect()
+        });
+        for ((index, _), color) in lights.iter().zip(colors.iter()) {
+            self.paint(*index, color, 1.0, BlendMode::Replace);
+        }
+    }
+
+    /// Returns the index, location and color of every light, in index order
+    /// Locations are in the frame set by `set_transform`
+    pub fn iter(&self) -> impl Iterator<Item = (usize, Loc<N>, Color)> + '_ {
+        self.with_colors(self.all_lights())
+    }
+
+    /// Calls `f` with the index, location and color of every light in index order, saving any
+    /// changes it makes to the color
+    pub fn for_each_mut<F: FnMut(usize, &Loc<N>, &mut Color)>(&mut self, mut f: F) {
+        for (index, loc, color) in self.iter().collect::<Vec<_>>() {
+            let mut updated = color;
+            f(index, &loc, &mut updated);
+            if updated != color {
+                self.paint(index, &updated, 1.0, BlendMode::Replace);
+            }
+        }
+    }
+
+    /// Returns the index, location, color and distance from `center` of every light within
+    /// `radius` of it, in index order
+    pub fn iter_in_radius(
+        &self,
+        center: &Loc<N>,
+        radius: f64,
+    ) -> impl Iterator<Item = (usize, Loc<N>, Color, f64)> + '_ {
+        let mut lights = self.lights_within_radius(center, radius);
+        lights.sort_by_key(|(index, _, _)| *index);
+        lights.into_iter().map(|(index, point, distance)| {
+            (
+                index,
+                Loc::cartesian(point),
+                self.get_color(index),
+                distance,
+            )
+        })
+    }
+
+    /// Returns the index, location, color and distance from the center of the box of every
+    /// light in the box between `lower_corner` and `upper_corner`, in index order
+    pub fn iter_in_box(
+        &self,
+        lower_corner: &Loc<N>,
+        upper_corner: &Loc<N>,
+    ) -> impl Iterator<Item = (usize, Loc<N>, Color, f64)> + '_ {
+        let center = lower_corner.lerp(upper_corner, 0.5);
+        let metric = *self.metric();
+        self.with_colors(self.lights_in_box(lower_corner, upper_corner))
+            .map(move |(index, loc, color)| {
+                let distance = metric.distance(&loc.coords, &center.coords);
+                (index, loc, color, distance)
+            })
+    }
+
     pub fn get_by_index(&mut self, index: usize) -> Color {
-        self.light_strip.get(index)
+        self.get_color(index)
     }
 
     pub fn set_by_index(&mut self, index: usize, color: &Color) {
-        self.light_strip.set(index, color);
+        self.paint(index, color, 1.0, BlendMode::Replace);
+    }
+
+    /// Gets the (r, g, b) of the light at `index`, where 1.0 is full brightness
+    /// With the frame buffer enabled, values can be above 1.0
+    pub fn get_by_index_hdr(&self, index: usize) -> [f32; 3] {
+        match &self.frame_buffer {
+            Some(frame_buffer) => frame_buffer.get(index),
+            None => {
+                let (r, g, b) = self.light_strip.get(index).float_components();
+                [r, g, b]
+            }
+        }
+    }
+
+    /// Sets the light at `index` to (r, g, b), where 1.0 is full brightness
+    /// Values above 1.0 are kept by the frame buffer and tone mapped by `show`; without the frame
+    /// buffer they are clamped
+    pub fn set_by_index_hdr(&mut self, index: usize, color: [f32; 3]) {
+        match &mut self.frame_buffer {
+            Some(frame_buffer) => frame_buffer.set(index, color),
+            None => {
+                let [r, g, b] = color;
+                let color = Color::from_unit(r as f64, g as f64, b as f64);
+                self.light_strip.set(index, &color);
+            }
+        }
+    }
+
+    /// Gets the color of the light at `index` including its white channel, for RGBW strips
+    pub fn get_by_index_rgbw(&mut self, index: usize) -> ColorW {
+        match &self.frame_buffer {
+            Some(frame_buffer) => {
+                ColorW::from_color(&frame_buffer.get_color(index), WhiteExtraction::None)
+            }
+            None => self.light_strip.get_rgbw(index),
+        }
+    }
+
+    /// Sets the light at `index` including its white channel, for RGBW strips
+    /// The frame buffer holds RGB, so with it enabled the white is mixed into the other channels
+    /// and split back out by the strip
+    pub fn set_by_index_rgbw(&mut self, index: usize, color: &ColorW) {
+        match &mut self.frame_buffer {
+            Some(_) => {
+                let color = color.to_color(WhiteExtraction::MinSubtraction);
+                self.paint(index, &color, 1.0, BlendMode::Replace);
+            }
+            None => self.light_strip.set_rgbw(index, color),
+        }
     }
 
     pub fn fill(&mut self, color: &Color) {
-        self.light_strip.fill(color)
+        match &mut self.frame_buffer {
+            Some(frame_buffer) => {
+                let (r, g, b) = color.float_components();
+                frame_buffer.fill([r, g, b]);
+            }
+            None => self.light_strip.fill(color),
+        }
     }
 
     pub fn show(&mut self) {
+        if let Some(frame_buffer) = &mut self.frame_buffer {
+            frame_buffer.write_to(&mut self.light_strip);
+        }
         self.light_strip.show()
     }
 
     pub fn number_lights(&self) -> usize {
         self.arrangement.number_lights()
     }
+
+    /// Returns the index and location of every light, in the frame set by `set_transform`
+    fn all_lights(&self) -> Vec<(usize, [f64; N])> {
+        let lights = self.arrangement.get_all();
+        match &self.frame {
+            Some((_, inverse)) => lights
+                .iter()
+                .map(|pt| (pt.data, inverse.apply_point(&pt.point)))
+                .collect(),
+            None => lights.iter().map(|pt| (pt.data, pt.point)).collect(),
+        }
+    }
+
+    /// Sorts `lights` by index and pairs each with its color
+    fn with_colors(
+        &self,
+        mut lights: Vec<(usize, [f64; N])>,
+    ) -> impl Iterator<Item = (usize, Loc<N>, Color)> + '_ {
+        lights.sort_by_key(|(index, _)| *index);
+        lights
+            .into_iter()
+            .map(|(index, point)| (index, Loc::cartesian(point), self.get_color(index)))
+    }
+
+    fn set_lights_by_fn<F: FnMut(&Loc<N>, usize) -> Color>(
+        &mut self,
+        lights: Vec<(usize, [f64; N])>,
+        mut f: F,
+    ) {
+        for (index, point) in lights {
+            let color = f(&Loc::cartesian(point), index);
+            self.paint(index, &color, 1.0, BlendMode::Replace);
+        }
+    }
+
+    /// Returns the index and signed distance of every light inside `shape` or less than `margin`
+    /// outside of it, checking only lights in the bounding box of the shape
+    fn distances_to_shape<S: Shape<N> + ?Sized>(
+        &self,
+        shape: &S,
+        margin: f64,
+    ) -> Vec<(usize, f64)> {
+        let (lower, upper) = shape.bounding_box();
+        let margin_loc = Loc::cartesian([margin; N]);
+        let (lower, upper) = (lower - margin_loc, upper + margin_loc);
+        if (0..N).any(|i| lower.coords[i] > upper.coords[i]) {
+            return vec![];
+        }
+        let candidates = if lower
+            .coords
+            .iter()
+            .chain(upper.coords.iter())
+            .all(|c| c.is_finite())
+        {
+            self.lights_in_box(&lower, &upper)
+        } else {
+            self.all_lights()
+        };
+        candidates
+            .into_iter()
+            .map(|(index, point)| (index, shape.signed_distance(&Loc::cartesian(point))))
+            .filter(|(_, distance)| *distance <= 0.0 || *distance < margin)
+            .collect()
+    }
+
+    /// Returns the index and signed distance from `plane` of every light with a distance from
+    /// `low` up to, but not including, `high`. Lights on the plane are always kept
+    /// Only lights in the box around the part of that slab of space inside the unit box are
+    /// checked, which relies on `Arrangement` rejecting lights outside of it. The box is found in
+    /// the coordinates of the arrangement so it stays tight for planes at any angle and in any
+    /// frame
+    fn distances_to_plane(&self, plane: &Plane<N>, low: f64, high: f64) -> Vec<(usize, f64)> {
+        let (normal, offset) = (plane.normal().coords, plane.offset());
+        // `normal · inverse(q) - offset` is `arrangement_normal · q - shift` for arrangement
+        // coordinates `q`
+        let (arrangement_normal, shift) = match &self.frame {
+            Some((_, inverse)) => {
+                let (matrix, translation) = (inverse.matrix(), inverse.offset());
+                let mut arrangement_normal = [0.0; N];
+                for (j, n) in arrangement_normal.iter_mut().enumerate() {
+                    *n = (0..N).map(|i| normal[i] * matrix[i][j]).sum();
+                }
+                let moved: f64 = (0..N).map(|i| normal[i] * translation[i]).sum();
+                (arrangement_normal, offset - moved)
+            }
+            None => (normal, offset),
+        };
+        let (lower, upper) = match slab_in_unit_box(&arrangement_normal, low + shift, high + shift)
+        {
+            Some(corners) => corners,
+            None => return vec![],
+        };
+        self.arrangement
+            .get_within_bounding_box(&Loc::cartesian(lower), &Loc::cartesian(upper))
+            .iter()
+            .map(|pt| {
+                let point = match &self.frame {
+                    Some((_, inverse)) => inverse.apply_point(&pt.point),
+                    None => pt.point,
+                };
+                (pt.data, plane.signed_distance(&Loc::cartesian(point)))
+            })
+            .filter(|(_, distance)| low <= *distance && (*distance <= 0.0 || *distance < high))
+            .collect()
+    }
+
+    /// Returns the index of the light closest to `loc` that is within `max_distance`
+    fn closest_index(&self, loc: &Loc<N>, max_distance: f64) -> Option<usize> {
+        match &self.frame {
+            Some(_) => self
+                .distances_within_radius(loc, max_distance)
+                .into_iter()
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(index, _)| index),
+            None => self
+                .arrangement
+                .get_closest(loc, max_distance)
+                .map(|pt| pt.data),
+        }
+    }
+
+    /// Returns the index and location of every light in the box between `lower_corner` and
+    /// `upper_corner`, in the frame set by `set_transform`
+    fn lights_in_box(
+        &self,
+        lower_corner: &Loc<N>,
+        upper_corner: &Loc<N>,
+    ) -> Vec<(usize, [f64; N])> {
+        let (transform, inverse) = match &self.frame {
+            Some(frame) => frame,
+            None => {
+                return self
+                    .arrangement
+                    .get_within_bounding_box(lower_corner, upper_corner)
+                    .iter()
+                    .map(|pt| (pt.data, pt.point))
+                    .collect()
+            }
+        };
+
+        let (lower, upper) = transform.bounding_box(&lower_corner.coords, &upper_corner.coords);
+        self.arrangement
+            .get_within_bounding_box(&Loc::cartesian(lower), &Loc::cartesian(upper))
+            .iter()
+            .map(|pt| (pt.data, inverse.apply_point(&pt.point)))
+            .filter(|(_, point)| {
+                (0..N).all(|i| {
+                    let (a, b) = (lower_corner.coords[i], upper_corner.coords[i]);
+                    a.min(b) <= point[i] && point[i] <= a.max(b)
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the index and normalized distance from the center of every light inside
+    /// `ellipsoid`, which is 1 at its surface
+    fn lights_in_ellipsoid(&self, ellipsoid: &Ellipsoid<N>) -> Vec<(usize, f64)> {
+        let (lower, upper) = ellipsoid.bounding_box();
+        self.lights_in_box(&lower, &upper)
+            .into_iter()
+            .map(|(index, point)| {
+                let distance = ellipsoid.normalized_distance(&Loc::cartesian(point));
+                (index, distance)
+            })
+            .filter(|(_, distance)| *distance < 1.0)
+            .collect()
+    }
+
+    /// Returns the index and distance from `center` of every light within `radius` of it
+    fn distances_within_radius(&self, center: &Loc<N>, radius: f64) -> Vec<(usize, f64)> {
+        self.lights_within_radius(center, radius)
+            .into_iter()
+            .map(|(index, _, distance)| (index, distance))
+            .collect()
+    }
+
+    /// Returns the index, location and distance from `center` of every light within `radius` of
+    /// it
+    fn lights_within_radius(&self, center: &Loc<N>, radius: f64) -> Vec<(usize, [f64; N], f64)> {
+        let metric = self.metric();
+        let (transform, inverse) = match &self.frame {
+            Some(frame) => frame,
+            None => {
+                return self
+                    .arrangement
+                    .get_within_radius(center, radius)
+                    .iter()
+                    .map(|pt| {
+                        let distance = metric.distance(&pt.point, &center.coords);
+                        (pt.data, pt.point, distance)
+                    })
+                    .collect()
+            }
+        };
+
+        let extents = metric.extents(radius);
+        let candidates = if *metric == Metric::Euclidean {
+            let (lower, upper) = transform.bounding_box_of_ball(&center.coords, radius);
+            self.arrangement
+                .get_within_bounding_box(&Loc::cartesian(lower), &Loc::cartesian(upper))
+        } else if extents.iter().all(|e| e.is_finite()) {
+            let extents = Loc::cartesian(extents);
+            let (lower, upper) =
+                transform.bounding_box(&(*center - extents).coords, &(*center + extents).coords);
+            self.arrangement
+                .get_within_bounding_box(&Loc::cartesian(lower), &Loc::cartesian(upper))
+        } else {
+            self.arrangement.get_all()
+        };
+        candidates
+            .iter()
+            .map(|pt| {
+                let point = inverse.apply_point(&pt.point);
+                (pt.data, point, metric.distance(&point, &center.coords))
+            })
+            .filter(|(_, _, distance)| *distance < radius)
+            .collect()
+    }
+
+    /// Returns the color of the light at `index`, from the frame buffer if it is enabled
+    #[inline]
+    fn get_color(&self, index: usize) -> Color {
+        match &self.frame_buffer {
+            Some(frame_buffer) => frame_buffer.get_color(index),
+            None => self.light_strip.get(index),
+        }
+    }
+
+    /// Blends `color` onto the light at `index` with `mode`, moving it only `intensity` in 0..1 of
+    /// the way to the blended color
+    /// Every setter goes through here so they all respect the frame buffer
+    #[inline]
+    fn paint(&mut self, index: usize, color: &Color, intensity: f64, mode: BlendMode) {
+        if let Some(frame_buffer) = &mut self.frame_buffer {
+            frame_buffer.blend(index, color, intensity, mode);
+            return;
+        }
+
+        match (mode, intensity) {
+            (BlendMode::Replace, intensity) if intensity >= 1.0 => {
+                self.light_strip.set(index, color)
+            }
+            _ => {
+                let current = self.light_strip.get(index);
+                let blended = mode.blend_with_intensity(&current, color, intensity);
+                self.light_strip.set(index, &blended);
+            }
+        }
+    }
+}
+
+/// Intensity of a light `distance` outside the edge of a region that fades out to nothing along
+/// `falloff` `edge_softness` past it
+fn edge_intensity(distance: f64, edge_softness: f64, falloff: &Falloff) -> f64 {
+    if distance <= 0.0 {
+        1.0
+    } else {
+        falloff.intensity(distance / edge_softness)
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use std::error::Error;
+    use std::{error::Error, f64::consts::PI};
 
-    use crate::{Loc, TestStrip, TestStripDisplayConfig};
+    use crate::{
+        math::distance, Capsule, ConvexHull, Dither, Ellipsoid, Falloff, Interpolation, Loc, Plane,
+        Polygon, Sphere, TestStrip, TestStripDisplayConfig, ToneMap,
+    };
 
     use super::*;
 
@@ -282,24 +1235,125 @@ mod test {
         light_arrangement.set_decreasing_intensity_merge(&Loc::cartesian([0.5, 0.5]), 1.0, &color1);
         light_arrangement.set_decreasing_intensity_merge(&Loc::cartesian([0.5, 0.5]), 1.0, &color2);
 
-        for i in [0.2, 0.4, 0.6, 0.8, 1.0] {
-            for j in [0.2, 0.4, 0.6, 0.8, 1.0] {
-                let dist = distance(&[i, j], &[0.5, 0.5]);
-                let expected_color_val = ((1.0 - clamp(dist / 1.0, 0.0, 1.0)) * 255.0) as u8;
+        for i in [0.2, 0.4, 0.6, 0.8, 1.0] {
+            for j in [0.2, 0.4, 0.6, 0.8, 1.0] {
+                let dist = distance(&[i, j], &[0.5, 0.5]);
+                let expected_color_val = ((1.0 - clamp(dist / 1.0, 0.0, 1.0)) * 255.0) as u8;
+
+                let color = light_arrangement.get_closest(&Loc::cartesian([i, j]), 0.1);
+                assert_eq!(
+                    color.unwrap(),
+                    Color {
+                        red: expected_color_val,
+                        green: expected_color_val,
+                        blue: 0
+                    }
+                );
+            }
+        }
+
+        return Ok(());
+    }
+
+    #[test]
+    fn set_blend() -> Result<(), Box<dyn Error>> {
+        let mut light_arrangement = make_light_arrangement()?;
+        light_arrangement.fill(&Color::rgb(100, 100, 0));
+
+        light_arrangement.set_closest_blend(
+            &Loc::cartesian([0.2, 0.2]),
+            0.1,
+            &Color::rgb(100, 0, 100),
+            BlendMode::Add,
+        );
+        assert_eq!(light_arrangement.get_by_index(0), Color::rgb(200, 100, 100));
+
+        light_arrangement.set_all_in_radius_blend(
+            &Loc::cartesian([0.6, 0.6]),
+            0.1,
+            &Color::rgb(0, 200, 200),
+            BlendMode::Average,
+        );
+        assert_eq!(light_arrangement.get_by_index(12), Color::rgb(50, 150, 100));
+        assert_eq!(light_arrangement.get_by_index(11), Color::rgb(100, 100, 0));
+
+        light_arrangement.set_all_in_box_blend(
+            &Loc::cartesian([0.7, 0.7]),
+            &Loc::cartesian([1.0, 1.0]),
+            &Color::rgb(255, 0, 255),
+            BlendMode::AlphaOver(0.5),
+        );
+        assert_eq!(light_arrangement.get_by_index(24), Color::rgb(178, 50, 128));
+        assert_eq!(light_arrangement.get_by_index(18), Color::rgb(178, 50, 128));
+        assert_eq!(light_arrangement.get_by_index(17), Color::rgb(100, 100, 0));
+
+        light_arrangement.set_decreasing_intensity_blend(
+            &Loc::cartesian([0.2, 1.0]),
+            0.3,
+            &Color::rgb(255, 255, 255),
+            &Falloff::Linear,
+            BlendMode::Multiply,
+        );
+        assert_eq!(light_arrangement.get_by_index(20), Color::rgb(100, 100, 0));
+        // the faded edge of a multiply by white leaves the light as it was
+        assert_eq!(light_arrangement.get_by_index(21), Color::rgb(100, 100, 0));
+        assert_eq!(light_arrangement.get_by_index(22), Color::rgb(100, 100, 0));
+        assert_eq!(light_arrangement.get_by_index(23), Color::rgb(178, 50, 128));
+
+        Ok(())
+    }
+
+    #[test]
+    fn set_rgba() -> Result<(), Box<dyn Error>> {
+        let mut light_arrangement = make_light_arrangement()?;
+        light_arrangement.fill(&Color::rgb(0, 0, 200));
+        let red = ColorA::rgba(200, 0, 0, 255);
 
-                let color = light_arrangement.get_closest(&Loc::cartesian([i, j]), 0.1);
-                assert_eq!(
-                    color.unwrap(),
-                    Color {
-                        red: expected_color_val,
-                        green: expected_color_val,
-                        blue: 0
-                    }
-                );
-            }
-        }
+        light_arrangement.set_closest_rgba(&Loc::cartesian([0.2, 0.2]), 0.1, &red, 0.5);
+        assert_eq!(light_arrangement.get_by_index(0), Color::rgb(100, 0, 100));
 
-        return Ok(());
+        light_arrangement.set_all_in_radius_rgba(
+            &Loc::cartesian([0.6, 0.6]),
+            0.1,
+            &ColorA::rgba(200, 0, 0, 102),
+            1.0,
+        );
+        assert_eq!(light_arrangement.get_by_index(12), Color::rgb(80, 0, 120));
+        assert_eq!(light_arrangement.get_by_index(11), Color::rgb(0, 0, 200));
+
+        light_arrangement.set_all_in_box_rgba(
+            &Loc::cartesian([0.7, 0.7]),
+            &Loc::cartesian([1.0, 1.0]),
+            &red,
+            0.0,
+        );
+        assert_eq!(light_arrangement.get_by_index(24), Color::rgb(0, 0, 200));
+
+        light_arrangement.set_decreasing_intensity_rgba(
+            &Loc::cartesian([0.2, 1.0]),
+            0.3,
+            &red,
+            1.0,
+            &Falloff::Linear,
+        );
+        assert_eq!(light_arrangement.get_by_index(20), Color::rgb(200, 0, 0));
+        assert_eq!(light_arrangement.get_by_index(21), Color::rgb(66, 0, 133));
+        assert_eq!(light_arrangement.get_by_index(22), Color::rgb(0, 0, 200));
+
+        // only a ninth of the way over two thirds of the way out
+        light_arrangement.fill(&Color::rgb(0, 0, 200));
+        light_arrangement.set_decreasing_intensity_rgba(
+            &Loc::cartesian([0.2, 1.0]),
+            0.3,
+            &red,
+            1.0,
+            &Falloff::Quadratic,
+        );
+        assert_eq!(light_arrangement.get_by_index(20), Color::rgb(200, 0, 0));
+        assert_eq!(light_arrangement.get_by_index(21), Color::rgb(22, 0, 177));
+        assert_eq!(light_arrangement.get_by_index(22), Color::rgb(0, 0, 200));
+
+        Ok(())
     }
 
     #[test]
@@ -409,6 +1463,124 @@ mod test {
         return Ok(());
     }
 
+    #[test]
+    fn set_gradients() -> Result<(), Box<dyn Error>> {
+        let mut light_arrangement = make_light_arrangement()?;
+        let gradient = Gradient::evenly_spaced(&[Color::rgb(0, 0, 0), Color::rgb(200, 0, 0)])?
+            .with_interpolation(Interpolation::Srgb);
+
+        light_arrangement.set_gradient_linear(
+            &Loc::cartesian([0.2, 0.0]),
+            &Loc::cartesian([1.0, 0.0]),
+            &gradient,
+        );
+        for (i, expected) in [0.2, 0.4, 0.6, 0.8, 1.0].iter().zip([0, 50, 100, 150, 200]) {
+            for j in [0.2, 0.4, 0.6, 0.8, 1.0] {
+                assert_eq!(
+                    light_arrangement.get_closest(&Loc::cartesian([*i, j]), 0.1),
+                    Some(Color::rgb(expected, 0, 0))
+                );
+            }
+        }
+
+        light_arrangement.set_gradient_radial(&Loc::cartesian([0.6, 0.6]), 0.4, &gradient);
+        assert_eq!(light_arrangement.get_by_index(12), Color::rgb(0, 0, 0));
+        assert_eq!(light_arrangement.get_by_index(11), Color::rgb(100, 0, 0));
+        assert_eq!(light_arrangement.get_by_index(22), Color::rgb(200, 0, 0));
+        assert_eq!(light_arrangement.get_by_index(0), Color::rgb(200, 0, 0));
+
+        // no radius leaves the lights alone, even one right at the center
+        light_arrangement.fill(&Color::rgb(0, 0, 7));
+        light_arrangement.set_gradient_radial(&Loc::cartesian([0.6, 0.6]), 0.0, &gradient);
+        for i in 0..25 {
+            assert_eq!(light_arrangement.get_by_index(i), Color::rgb(0, 0, 7));
+        }
+
+        light_arrangement.set_gradient_by_index(&gradient);
+        assert_eq!(light_arrangement.get_by_index(0), Color::rgb(0, 0, 0));
+        assert_eq!(light_arrangement.get_by_index(12), Color::rgb(100, 0, 0));
+        assert_eq!(light_arrangement.get_by_index(24), Color::rgb(200, 0, 0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn frame_buffer() -> Result<(), Box<dyn Error>> {
+        let mut light_arrangement = make_light_arrangement()?;
+        light_arrangement.set_by_index(0, &Color::rgb(10, 20, 30));
+        light_arrangement.enable_frame_buffer(FrameBufferConfig::new(ToneMap::Clamp, Dither::None));
+        assert_eq!(light_arrangement.get_by_index(0), Color::rgb(10, 20, 30));
+
+        // Overlapping effects add up past full brightness and are clamped when shown
+        light_arrangement.fill(&Color::rgb(0, 0, 0));
+        for _ in 0..3 {
+            light_arrangement.set_all_in_radius_blend(
+                &Loc::cartesian([0.6, 0.6]),
+                0.1,
+                &Color::rgb(200, 100, 0),
+                BlendMode::Add,
+            );
+        }
+        let [r, g, b] = light_arrangement.get_by_index_hdr(12);
+        assert!((r - 600.0 / 255.0).abs() < 0.0001);
+        assert!((g - 300.0 / 255.0).abs() < 0.0001);
+        assert_eq!(b, 0.0);
+        assert_eq!(light_arrangement.get_by_index(12), Color::rgb(255, 255, 0));
+
+        // Nothing reaches the strip until it is shown
+        assert_eq!(light_arrangement.light_strip.get(12), Color::rgb(0, 0, 0));
+        light_arrangement.show();
+        assert_eq!(
+            light_arrangement.light_strip.get(12),
+            Color::rgb(255, 255, 0)
+        );
+
+        // Fading out by halves keeps a fraction of a step that 8 bit colors would lose
+        light_arrangement.set_by_index_hdr(3, [1.0, 1.0, 1.0]);
+        for _ in 0..9 {
+            light_arrangement.set_by_index_hdr(3, {
+                let [r, g, b] = light_arrangement.get_by_index_hdr(3);
+                [r / 2.0, g / 2.0, b / 2.0]
+            });
+        }
+        assert_eq!(light_arrangement.get_by_index_hdr(3), [1.0 / 512.0; 3]);
+
+        light_arrangement.disable_frame_buffer();
+        assert_eq!(light_arrangement.get_by_index(12), Color::rgb(255, 255, 0));
+        Ok(())
+    }
+
+    #[test]
+    fn sparse_indices() -> Result<(), Box<dyn Error>> {
+        // lights 0, 5 and 9 of a strip where the rest are not part of the arrangement
+        let arrangement_config = ArrangementConfig {
+            light_locations: vec![([0.1], 0), ([0.5], 5), ([0.9], 9)],
+            number_children_for_division: 1,
+        };
+        let test_strip = TestStrip::new(&arrangement_config, &TestStripDisplayConfig::default());
+        let mut light_arrangement = LightArrangement::new(test_strip, arrangement_config)?;
+        let red = Color::rgb(255, 0, 0);
+        let black = Color::rgb(0, 0, 0);
+        assert_eq!(light_arrangement.number_lights(), 3);
+
+        light_arrangement.enable_frame_buffer(FrameBufferConfig::new(ToneMap::Clamp, Dither::None));
+        light_arrangement.fill(&black);
+        light_arrangement.set_all_in_radius(&Loc::cartesian([0.9]), 0.1, &red);
+        light_arrangement.show();
+        assert_eq!(light_arrangement.light_strip.get(9), red);
+        assert_eq!(light_arrangement.light_strip.get(5), black);
+
+        // the gradient runs from light 0 to the highest index
+        let gradient =
+            Gradient::evenly_spaced(&[black, red])?.with_interpolation(Interpolation::Srgb);
+        light_arrangement.disable_frame_buffer();
+        light_arrangement.set_gradient_by_index(&gradient);
+        assert_eq!(light_arrangement.get_by_index(0), black);
+        assert!((141..=142).contains(&light_arrangement.get_by_index(5).red));
+        assert_eq!(light_arrangement.get_by_index(9), red);
+        Ok(())
+    }
+
     #[test]
     fn get_and_set_by_index() -> Result<(), Box<dyn Error>> {
         let mut light_arrangement = make_light_arrangement()?;
@@ -431,6 +1603,701 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn get_and_set_by_index_rgbw() -> Result<(), Box<dyn Error>> {
+        let mut light_arrangement = make_light_arrangement()?;
+
+        // The test strip has no white LED, so white is mixed into the other channels
+        light_arrangement.set_by_index_rgbw(3, &ColorW::rgbw(100, 0, 0, 50));
+        assert_eq!(light_arrangement.get_by_index(3), Color::rgb(150, 50, 50));
+        assert_eq!(
+            light_arrangement.get_by_index_rgbw(3),
+            ColorW::rgbw(150, 50, 50, 0)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn set_in_transformed_frame() -> Result<(), Box<dyn Error>> {
+        let mut light_arrangement = make_light_arrangement()?;
+        let red = Color::rgb(255, 0, 0);
+        let black = Color::rgb(0, 0, 0);
+        light_arrangement.fill(&black);
+
+        // a quarter turn around the middle of the grid moves the bottom row to the right column
+        let quarter_turn = Transform::rotation(0, 1, PI / 2.0).about(&[0.6, 0.6]);
+        light_arrangement.set_transform(quarter_turn)?;
+        light_arrangement.set_all_in_box(
+            &Loc::cartesian([0.15, 0.15]),
+            &Loc::cartesian([0.45, 0.25]),
+            &red,
+        );
+        for i in 0..25 {
+            let expected = if i == 4 || i == 9 { red } else { black };
+            assert_eq!(light_arrangement.get_by_index(i), expected);
+        }
+
+        light_arrangement.fill(&black);
+        light_arrangement.set_closest(&Loc::cartesian([0.2, 0.2]), 0.1, &red);
+        assert_eq!(light_arrangement.get_by_index(4), red);
+        assert_eq!(
+            light_arrangement.get_closest(&Loc::cartesian([0.21, 0.2]), 0.1),
+            Some(red)
+        );
+        assert_eq!(light_arrangement.get_by_index(0), black);
+
+        // stretching the frame stretches circles into ellipses
+        light_arrangement.fill(&black);
+        light_arrangement.set_transform(Transform::scale_axes([2.0, 1.0]).about(&[0.6, 0.6]))?;
+        light_arrangement.set_all_in_radius(&Loc::cartesian([0.6, 0.6]), 0.15, &red);
+        for i in 0..25 {
+            let expected = if (11..=13).contains(&i) { red } else { black };
+            assert_eq!(light_arrangement.get_by_index(i), expected);
+        }
+
+        // transforms that cannot be undone are refused
+        assert!(light_arrangement
+            .set_transform(Transform::scale_axes([1.0, 0.0]))
+            .is_err());
+        assert_eq!(
+            light_arrangement.transform(),
+            Transform::scale_axes([2.0, 1.0]).about(&[0.6, 0.6])
+        );
+
+        light_arrangement.clear_transform();
+        light_arrangement.fill(&black);
+        light_arrangement.set_closest(&Loc::cartesian([0.2, 0.2]), 0.1, &red);
+        assert_eq!(light_arrangement.get_by_index(0), red);
+        assert_eq!(light_arrangement.transform(), Transform::identity());
+        Ok(())
+    }
+
+    #[test]
+    fn set_path() -> Result<(), Box<dyn Error>> {
+        let mut light_arrangement = make_light_arrangement()?;
+        let red = Color::rgb(255, 0, 0);
+        let black = Color::rgb(0, 0, 0);
+        light_arrangement.fill(&black);
+
+        // an L along the bottom row and up the right column
+        let path = Path::polyline(&[
+            Loc::cartesian([0.2, 0.2]),
+            Loc::cartesian([1.0, 0.2]),
+            Loc::cartesian([1.0, 1.0]),
+        ])?;
+        light_arrangement.set_path(&path, 0.1, &red);
+        for i in 0..25 {
+            let color = light_arrangement.get_by_index(i);
+            if i < 5 || i % 5 == 4 {
+                assert!(color.red >= 254);
+            } else {
+                assert_eq!(color, black);
+            }
+        }
+
+        // lights between the path and `thickness` away are dimmed
+        light_arrangement.fill(&black);
+        let path = Path::line(Loc::cartesian([0.0, 0.3]), Loc::cartesian([1.0, 0.3]));
+        light_arrangement.set_path(&path, 0.2, &red);
+        for i in 0..10 {
+            let color = light_arrangement.get_by_index(i);
+            assert!((126..=128).contains(&color.red));
+        }
+        for i in 10..25 {
+            assert_eq!(light_arrangement.get_by_index(i), black);
+        }
+
+        // or kept at full intensity with a hard edge
+        light_arrangement.fill(&black);
+        let hard = Falloff::SoftStep(0.0);
+        light_arrangement.set_path_blend(&path, 0.2, &red, &hard, BlendMode::Max);
+        for i in 0..25 {
+            let expected = if i < 10 { red } else { black };
+            assert_eq!(light_arrangement.get_by_index(i), expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn set_with_metric() -> Result<(), Box<dyn Error>> {
+        let mut light_arrangement = make_light_arrangement()?;
+        let red = Color::rgb(255, 0, 0);
+        let black = Color::rgb(0, 0, 0);
+        let center = Loc::cartesian([0.6, 0.6]);
+
+        // squares
+        light_arrangement.fill(&black);
+        light_arrangement.set_metric(Metric::Chebyshev);
+        light_arrangement.set_all_in_radius(&center, 0.3, &red);
+        for i in 0..25 {
+            let (row, col) = (i / 5, i % 5);
+            let inside = (1..=3).contains(&row) && (1..=3).contains(&col);
+            let expected = if inside { red } else { black };
+            assert_eq!(light_arrangement.get_by_index(i), expected);
+        }
+
+        // and the falloff fades out in squares too
+        light_arrangement.fill(&black);
+        light_arrangement.set_decreasing_intensity(&center, 0.4, &red);
+        assert_eq!(light_arrangement.get_by_index(12), red);
+        let side = light_arrangement.get_by_index(11);
+        assert!(side.red > 0 && side.red < 255);
+        assert_eq!(light_arrangement.get_by_index(6), side);
+        assert_eq!(light_arrangement.get_by_index(18), side);
+
+        // diamonds
+        light_arrangement.fill(&black);
+        light_arrangement.set_metric(Metric::Manhattan);
+        light_arrangement.set_all_in_radius(&center, 0.3, &red);
+        for i in 0..25 {
+            let expected = if [7, 11, 12, 13, 17].contains(&i) {
+                red
+            } else {
+                black
+            };
+            assert_eq!(light_arrangement.get_by_index(i), expected);
+        }
+
+        // a row, by ignoring the vertical axis, even in a transformed frame
+        light_arrangement.fill(&black);
+        light_arrangement.set_metric(Metric::WeightedEuclidean([0.0, 1.0]));
+        light_arrangement.set_transform(Transform::translation([0.0, 0.2]))?;
+        light_arrangement.set_all_in_radius(&Loc::cartesian([0.0, 0.0]), 0.1, &red);
+        for i in 0..25 {
+            let expected = if i < 5 { red } else { black };
+            assert_eq!(light_arrangement.get_by_index(i), expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn set_along_strip() -> Result<(), Box<dyn Error>> {
+        let mut light_arrangement = make_light_arrangement()?;
+        let red = Color::rgb(255, 0, 0);
+        let black = Color::rgb(0, 0, 0);
+        light_arrangement.fill(&black);
+
+        // rows are wired left to right, so the wire jumps 0.8 back across at the end of each row
+        let topology = light_arrangement.topology();
+        assert!((topology.arc_length(4).unwrap() - 0.8).abs() < 0.0001);
+        let row_jump = (0.8_f64.powi(2) + 0.2_f64.powi(2)).sqrt();
+        assert!((topology.arc_length(5).unwrap() - (0.8 + row_jump)).abs() < 0.0001);
+
+        // with each row wired separately, the next row starts where the last one ended
+        light_arrangement.set_strip_breaks(&[4, 9, 14, 19]);
+        assert!((light_arrangement.topology().arc_length(5).unwrap() - 0.8).abs() < 0.0001);
+        assert_eq!(light_arrangement.topology().segments().len(), 5);
+
+        light_arrangement.set_along_strip(0.2, 0.3, &red);
+        assert_eq!(light_arrangement.get_by_index(1), red);
+        for i in [0, 2] {
+            let color = light_arrangement.get_by_index(i);
+            assert!(color.red > 0 && color.red < 255);
+        }
+        for i in 3..25 {
+            assert_eq!(light_arrangement.get_by_index(i), black);
+        }
+
+        // or at full intensity out to a hard edge
+        light_arrangement.fill(&black);
+        let hard = Falloff::SoftStep(0.0);
+        light_arrangement.set_along_strip_blend(0.2, 0.3, &red, &hard, BlendMode::Replace);
+        for i in 0..25 {
+            let expected = if i < 3 { red } else { black };
+            assert_eq!(light_arrangement.get_by_index(i), expected);
+        }
+
+        // the end of one row and the start of the next are at the same point along the wire
+        light_arrangement.fill(&black);
+        light_arrangement.set_along_strip(0.8, 0.1, &red);
+        for i in 0..25 {
+            let expected = if i == 4 || i == 5 { red } else { black };
+            assert_eq!(light_arrangement.get_by_index(i), expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn set_shape() -> Result<(), Box<dyn Error>> {
+        let mut light_arrangement = make_light_arrangement()?;
+        let red = Color::rgb(255, 0, 0);
+        let black = Color::rgb(0, 0, 0);
+
+        // a bar across the middle row
+        light_arrangement.fill(&black);
+        let bar = Capsule::new(Loc::cartesian([0.2, 0.6]), Loc::cartesian([1.0, 0.6]), 0.05);
+        light_arrangement.set_shape(&bar, &red, 0.0);
+        for i in 0..25 {
+            let expected = if (10..15).contains(&i) { red } else { black };
+            assert_eq!(light_arrangement.get_by_index(i), expected);
+        }
+
+        // soft edges reach half brightness onto the rows beside it
+        light_arrangement.fill(&black);
+        light_arrangement.set_shape(&bar, &red, 0.3);
+        for i in 0..25 {
+            let color = light_arrangement.get_by_index(i);
+            match i {
+                10..=14 => assert_eq!(color, red),
+                5..=9 | 15..=19 => assert!((126..=128).contains(&color.red)),
+                _ => assert_eq!(color, black),
+            }
+        }
+
+        // shapes that go on forever
+        light_arrangement.fill(&black);
+        let shape: Box<dyn Shape<2>> = Box::new(Plane::new(Loc::cartesian([1.0, 0.0]), 0.5));
+        light_arrangement.set_shape(shape.as_ref(), &red, 0.0);
+        for i in 0..25 {
+            let expected = if i % 5 < 2 { red } else { black };
+            assert_eq!(light_arrangement.get_by_index(i), expected);
+        }
+
+        // a ring made by cutting the middle out of a circle, with nothing left of shapes that miss
+        light_arrangement.fill(&black);
+        let center = Loc::cartesian([0.6, 0.6]);
+        let ring = Sphere::new(center, 0.25).difference(Sphere::new(center, 0.15));
+        light_arrangement.set_shape(&ring, &red, 0.0);
+        for i in 0..25 {
+            let expected = if [7, 11, 13, 17].contains(&i) {
+                red
+            } else {
+                black
+            };
+            assert_eq!(light_arrangement.get_by_index(i), expected);
+        }
+        let nothing = Sphere::new(Loc::cartesian([0.2, 0.2]), 0.1)
+            .intersection(Sphere::new(Loc::cartesian([0.8, 0.8]), 0.1));
+        light_arrangement.set_shape(&nothing, &black, 0.0);
+        assert_eq!(light_arrangement.get_by_index(7), red);
+
+        // shapes are drawn in the transformed frame like everything else
+        light_arrangement.fill(&black);
+        light_arrangement.set_transform(Transform::rotation(0, 1, PI / 2.0).about(&[0.6, 0.6]))?;
+        light_arrangement.set_shape(&Sphere::new(Loc::cartesian([0.2, 0.2]), 0.05), &red, 0.0);
+        for i in 0..25 {
+            let expected = if i == 4 { red } else { black };
+            assert_eq!(light_arrangement.get_by_index(i), expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn set_line() -> Result<(), Box<dyn Error>> {
+        let mut light_arrangement = make_light_arrangement()?;
+        let red = Color::rgb(255, 0, 0);
+        let black = Color::rgb(0, 0, 0);
+
+        // corner to corner
+        light_arrangement.fill(&black);
+        light_arrangement.set_line(
+            &Loc::cartesian([0.2, 0.2]),
+            &Loc::cartesian([1.0, 1.0]),
+            0.05,
+            &red,
+        );
+        for i in 0..25 {
+            let expected = if i % 6 == 0 { red } else { black };
+            assert_eq!(light_arrangement.get_by_index(i), expected);
+        }
+
+        // stops at its ends
+        light_arrangement.fill(&black);
+        light_arrangement.set_line(
+            &Loc::cartesian([0.3, 0.4]),
+            &Loc::cartesian([0.5, 0.4]),
+            0.15,
+            &red,
+        );
+        for i in 0..25 {
+            let expected = if [5, 6, 7].contains(&i) { red } else { black };
+            assert_eq!(light_arrangement.get_by_index(i), expected);
+        }
+
+        // fades out toward the edges
+        light_arrangement.fill(&black);
+        light_arrangement.set_line_decreasing_intensity(
+            &Loc::cartesian([0.0, 0.5]),
+            &Loc::cartesian([1.0, 0.5]),
+            0.2,
+            &red,
+        );
+        for i in 0..25 {
+            let color = light_arrangement.get_by_index(i);
+            if (5..15).contains(&i) {
+                assert!((126..=128).contains(&color.red));
+            } else {
+                assert_eq!(color, black);
+            }
+        }
+
+        light_arrangement.set_line_decreasing_intensity_blend(
+            &Loc::cartesian([0.2, 0.2]),
+            &Loc::cartesian([1.0, 0.2]),
+            0.1,
+            &Color::rgb(0, 0, 255),
+            &Falloff::Linear,
+            BlendMode::Add,
+        );
+        let blue = light_arrangement.get_by_index(2);
+        assert_eq!((blue.red, blue.green), (0, 0));
+        assert!(blue.blue >= 254);
+        Ok(())
+    }
+
+    #[test]
+    fn set_half_space_and_slab() -> Result<(), Box<dyn Error>> {
+        let mut light_arrangement = make_light_arrangement()?;
+        let red = Color::rgb(255, 0, 0);
+        let black = Color::rgb(0, 0, 0);
+
+        // everything below the diagonal x + y = 1.1, fading out over the next diagonal
+        light_arrangement.fill(&black);
+        let diagonal = Plane::new(Loc::cartesian([1.0, 1.0]), 1.1);
+        light_arrangement.set_half_space(&diagonal, 0.2 / 2.0_f64.sqrt(), &red);
+        for i in 0..25 {
+            let color = light_arrangement.get_by_index(i);
+            match i / 5 + i % 5 {
+                0..=3 => assert_eq!(color, red),
+                4 => assert!((126..=128).contains(&color.red)),
+                _ => assert_eq!(color, black),
+            }
+        }
+
+        // the middle row, fading out over the rows next to it
+        light_arrangement.fill(&black);
+        let middle_row = Plane::new(Loc::cartesian([0.0, 1.0]), 0.6);
+        light_arrangement.set_slab(&middle_row, 0.2, 0.2, &red);
+        for i in 0..25 {
+            let color = light_arrangement.get_by_index(i);
+            match i / 5 {
+                2 => assert_eq!(color, red),
+                1 | 3 => assert!((126..=128).contains(&color.red)),
+                _ => assert_eq!(color, black),
+            }
+        }
+
+        // a plane in a frame shifted right
+        light_arrangement.fill(&black);
+        light_arrangement.set_transform(Transform::translation([0.2, 0.0]))?;
+        let vertical = Plane::new(Loc::cartesian([1.0, 0.0]), 0.5);
+        light_arrangement.set_half_space(&vertical, 0.0, &red);
+        for i in 0..25 {
+            let expected = if i % 5 <= 2 { red } else { black };
+            assert_eq!(light_arrangement.get_by_index(i), expected);
+        }
+
+        // a slab outside the arrangement
+        light_arrangement.clear_transform();
+        light_arrangement.fill(&black);
+        let outside = Plane::new(Loc::cartesian([1.0, 1.0]), 3.0);
+        light_arrangement.set_slab_blend(
+            &outside,
+            0.5,
+            0.1,
+            &red,
+            &Falloff::Linear,
+            BlendMode::Add,
+        );
+        for i in 0..25 {
+            assert_eq!(light_arrangement.get_by_index(i), black);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn set_polygons() -> Result<(), Box<dyn Error>> {
+        let mut light_arrangement = make_light_arrangement()?;
+        let red = Color::rgb(255, 0, 0);
+        let black = Color::rgb(0, 0, 0);
+
+        // the triangle below the diagonal, from its corners or from the sides they lie on
+        let from_vertices = ConvexHull::from_vertices(&[
+            Loc::cartesian([0.1, 0.1]),
+            Loc::cartesian([1.0, 0.1]),
+            Loc::cartesian([0.1, 1.0]),
+        ])?;
+        let from_half_spaces = ConvexHull::from_half_spaces(&[
+            (Loc::cartesian([-1.0, 0.0]), -0.1),
+            (Loc::cartesian([0.0, -1.0]), -0.1),
+            (Loc::cartesian([1.0, 1.0]), 1.1),
+        ]);
+        for triangle in [from_vertices, from_half_spaces] {
+            light_arrangement.fill(&black);
+            light_arrangement.set_shape(&triangle, &red, 0.0);
+            for i in 0..25 {
+                let expected = if i / 5 + i % 5 <= 3 { red } else { black };
+                assert_eq!(light_arrangement.get_by_index(i), expected);
+            }
+        }
+
+        // an L that is not convex
+        light_arrangement.fill(&black);
+        let l = Polygon::new(&[
+            Loc::cartesian([0.1, 0.1]),
+            Loc::cartesian([1.1, 0.1]),
+            Loc::cartesian([1.1, 0.5]),
+            Loc::cartesian([0.5, 0.5]),
+            Loc::cartesian([0.5, 1.1]),
+            Loc::cartesian([0.1, 1.1]),
+        ])?;
+        light_arrangement.set_shape(&l, &red, 0.0);
+        for i in 0..25 {
+            let expected = if i < 10 || i % 5 <= 1 { red } else { black };
+            assert_eq!(light_arrangement.get_by_index(i), expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn set_with_falloff() -> Result<(), Box<dyn Error>> {
+        let mut light_arrangement = make_light_arrangement()?;
+        let red = Color::rgb(255, 0, 0);
+        let black = Color::rgb(0, 0, 0);
+        let center = Loc::cartesian([0.6, 0.6]);
+
+        // a quarter as bright halfway out
+        light_arrangement.fill(&black);
+        light_arrangement.set_decreasing_intensity_blend(
+            &center,
+            0.4,
+            &red,
+            &Falloff::Quadratic,
+            BlendMode::Replace,
+        );
+        assert!(light_arrangement.get_by_index(12).red >= 254);
+        for i in [7, 11, 13, 17] {
+            assert!((62..=64).contains(&light_arrangement.get_by_index(i).red));
+        }
+        // the corners are far dimmer than the 74 a linear falloff gives
+        assert!((20..=22).contains(&light_arrangement.get_by_index(6).red));
+        assert_eq!(light_arrangement.get_by_index(2), black);
+
+        // the falloff only applies to the call it is passed to
+        light_arrangement.fill(&black);
+        light_arrangement.set_decreasing_intensity(&center, 0.4, &red);
+        for i in [7, 11, 13, 17] {
+            assert!((126..=128).contains(&light_arrangement.get_by_index(i).red));
+        }
+        assert!((73..=75).contains(&light_arrangement.get_by_index(6).red));
+
+        let steps = Falloff::custom(|t| if t < 0.4 { 1.0 } else { 0.5 });
+        light_arrangement.fill(&black);
+        light_arrangement.set_decreasing_intensity_blend(
+            &center,
+            0.4,
+            &red,
+            &steps,
+            BlendMode::Max,
+        );
+        assert!(light_arrangement.get_by_index(12).red >= 254);
+        for i in [7, 11, 13, 17] {
+            assert!((126..=128).contains(&light_arrangement.get_by_index(i).red));
+        }
+
+        // shape edges fade along it too
+        let hard = Falloff::SoftStep(0.0);
+        let in_bar = |i: usize| if (5..20).contains(&i) { red } else { black };
+        light_arrangement.fill(&black);
+        let bar = Capsule::new(Loc::cartesian([0.2, 0.6]), Loc::cartesian([1.0, 0.6]), 0.05);
+        light_arrangement.set_shape_blend(&bar, &red, 0.3, &hard, BlendMode::Replace);
+        for i in 0..25 {
+            assert_eq!(light_arrangement.get_by_index(i), in_bar(i));
+        }
+        light_arrangement.fill(&black);
+        let plane = Plane::new(Loc::cartesian([0.0, 1.0]), 0.6);
+        light_arrangement.set_slab_blend(&plane, 0.1, 0.3, &red, &hard, BlendMode::Replace);
+        for i in 0..25 {
+            assert_eq!(light_arrangement.get_by_index(i), in_bar(i));
+        }
+
+        // while the plain setters keep fading linearly
+        light_arrangement.fill(&black);
+        light_arrangement.set_shape(&bar, &red, 0.3);
+        for i in [5, 9, 15, 19] {
+            assert!((126..=128).contains(&light_arrangement.get_by_index(i).red));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn set_by_fn() -> Result<(), Box<dyn Error>> {
+        let mut light_arrangement = make_light_arrangement()?;
+        let black = Color::rgb(0, 0, 0);
+        let by_location = |loc: &Loc<2>, _| {
+            Color::rgb(
+                (loc.coords[0] * 250.0) as u8,
+                0,
+                (loc.coords[1] * 250.0) as u8,
+            )
+        };
+
+        light_arrangement.set_by_fn(by_location);
+        for i in 0..25 {
+            let color = light_arrangement.get_by_index(i);
+            assert!((color.red as i32 - 50 * (i as i32 % 5 + 1)).abs() <= 1);
+            assert!((color.blue as i32 - 50 * (i as i32 / 5 + 1)).abs() <= 1);
+        }
+
+        // the same colors worked out on other threads
+        let expected: Vec<Color> = (0..25).map(|i| light_arrangement.get_by_index(i)).collect();
+        light_arrangement.fill(&black);
+        light_arrangement.set_by_fn_parallel(by_location);
+        for (i, color) in expected.iter().enumerate() {
+            assert_eq!(light_arrangement.get_by_index(i), *color);
+        }
+
+        // indices are passed along too
+        light_arrangement.set_by_fn(|_, index| Color::rgb(index as u8, 0, 0));
+        for i in 0..25 {
+            assert_eq!(light_arrangement.get_by_index(i), Color::rgb(i as u8, 0, 0));
+        }
+
+        light_arrangement.fill(&black);
+        let mut called = 0;
+        light_arrangement.set_by_fn_in_box(
+            &Loc::cartesian([0.3, 0.3]),
+            &Loc::cartesian([0.7, 0.5]),
+            |_, _| {
+                called += 1;
+                Color::rgb(255, 0, 0)
+            },
+        );
+        assert_eq!(called, 2);
+        for i in 0..25 {
+            let expected = if [6, 7].contains(&i) { 255 } else { 0 };
+            assert_eq!(light_arrangement.get_by_index(i).red, expected);
+        }
+
+        // locations are in the frame
+        light_arrangement.fill(&black);
+        light_arrangement.set_transform(Transform::translation([0.2, 0.2]))?;
+        light_arrangement.set_by_fn_in_radius(&Loc::cartesian([0.4, 0.4]), 0.1, |loc, index| {
+            assert_eq!(index, 12);
+            assert!(loc.distance_to(&Loc::cartesian([0.4, 0.4])) < 0.0001);
+            Color::rgb(0, 255, 0)
+        });
+        for i in 0..25 {
+            let expected = if i == 12 { 255 } else { 0 };
+            assert_eq!(light_arrangement.get_by_index(i).green, expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn iterators() -> Result<(), Box<dyn Error>> {
+        let mut light_arrangement = make_light_arrangement()?;
+        light_arrangement.set_by_fn(|_, index| Color::rgb(index as u8, 0, 0));
+
+        let lights: Vec<(usize, Loc<2>, Color)> = light_arrangement.iter().collect();
+        assert_eq!(lights.len(), 25);
+        for (i, (index, loc, color)) in lights.iter().enumerate() {
+            assert_eq!(*index, i);
+            let expected = [0.2 * (i % 5 + 1) as f64, 0.2 * (i / 5 + 1) as f64];
+            assert!(loc.distance_to(&Loc::cartesian(expected)) < 0.0001);
+            assert_eq!(*color, Color::rgb(i as u8, 0, 0));
+        }
+
+        // double every light in the left half
+        light_arrangement.for_each_mut(|_, loc, color| {
+            if loc.coords[0] < 0.5 {
+                color.red *= 2;
+            }
+        });
+        for i in 0..25 {
+            let expected = if i % 5 < 2 { 2 * i } else { i };
+            assert_eq!(
+                light_arrangement.get_by_index(i),
+                Color::rgb(expected as u8, 0, 0)
+            );
+        }
+
+        let center = Loc::cartesian([0.6, 0.6]);
+        let near: Vec<(usize, f64)> = light_arrangement
+            .iter_in_radius(&center, 0.25)
+            .map(|(index, _, _, distance)| (index, distance))
+            .collect();
+        assert_eq!(
+            near.iter().map(|(index, _)| *index).collect::<Vec<usize>>(),
+            vec![7, 11, 12, 13, 17]
+        );
+        assert!(near[2].1 < 0.0001);
+        assert!(near
+            .iter()
+            .filter(|(index, _)| *index != 12)
+            .all(|(_, distance)| (distance - 0.2).abs() < 0.0001));
+
+        let boxed: Vec<(usize, Color, f64)> = light_arrangement
+            .iter_in_box(&Loc::cartesian([0.3, 0.3]), &Loc::cartesian([0.9, 0.5]))
+            .map(|(index, _, color, distance)| (index, color, distance))
+            .collect();
+        assert_eq!(boxed.len(), 3);
+        assert_eq!(boxed[0].0, 6);
+        assert_eq!(boxed[0].1, Color::rgb(12, 0, 0));
+        assert!((boxed[1].2 - 0.0).abs() < 0.0001);
+        assert!((boxed[2].2 - 0.2).abs() < 0.0001);
+        Ok(())
+    }
+
+    #[test]
+    fn set_ellipsoid() -> Result<(), Box<dyn Error>> {
+        let mut light_arrangement = make_light_arrangement()?;
+        let red = Color::rgb(255, 0, 0);
+        let black = Color::rgb(0, 0, 0);
+
+        // a wide glow across the middle row
+        light_arrangement.fill(&black);
+        let glow = Ellipsoid::new(Loc::cartesian([0.6, 0.6]), [0.5, 0.1]);
+        light_arrangement.set_all_in_ellipsoid(&glow, &red);
+        for i in 0..25 {
+            let expected = if (10..15).contains(&i) { red } else { black };
+            assert_eq!(light_arrangement.get_by_index(i), expected);
+        }
+
+        // turned upright it covers the middle column, fading toward the ends
+        light_arrangement.fill(&black);
+        let upright = Ellipsoid::new(Loc::cartesian([0.6, 0.6]), [0.4, 0.1])
+            .with_rotation(Transform::rotation(0, 1, PI / 2.0));
+        light_arrangement.set_ellipsoid_decreasing_intensity(&upright, &red);
+        for i in 0..25 {
+            let color = light_arrangement.get_by_index(i);
+            match i {
+                12 => assert!(color.red >= 254),
+                7 | 17 => assert!((126..=128).contains(&color.red)),
+                _ => assert_eq!(color, black),
+            }
+        }
+
+        light_arrangement.set_all_in_ellipsoid_blend(
+            &Ellipsoid::new(Loc::cartesian([0.2, 0.2]), [0.1, 0.1]),
+            &Color::rgb(0, 0, 255),
+            BlendMode::Add,
+        );
+        assert_eq!(light_arrangement.get_by_index(0), Color::rgb(0, 0, 255));
+        light_arrangement.set_ellipsoid_decreasing_intensity_blend(
+            &upright,
+            &Color::rgb(0, 255, 0),
+            &Falloff::Linear,
+            BlendMode::Max,
+        );
+        let color = light_arrangement.get_by_index(12);
+        assert!(color.red >= 254 && color.green >= 254);
+
+        // it is a shape like any other, so it can have a hole cut out of it
+        light_arrangement.fill(&black);
+        let ring = glow.difference(Sphere::new(Loc::cartesian([0.6, 0.6]), 0.1));
+        light_arrangement.set_shape(&ring, &red, 0.0);
+        for i in 0..25 {
+            let expected = if [10, 11, 13, 14].contains(&i) {
+                red
+            } else {
+                black
+            };
+            assert_eq!(light_arrangement.get_by_index(i), expected);
+        }
+        Ok(())
+    }
+
     #[test]
     fn number_lights() -> Result<(), Box<dyn Error>> {
         let light_arrangement = make_light_arrangement()?;
@@ -1,8 +1,12 @@
 /// Captures how the light indeces are mapped to positions in N-dimensinoal space
 mod arrangement;
 mod arrangement_config;
+mod frame_buffer;
 mod light_arrangement;
+mod topology;
 
 pub use arrangement::Arrangement;
 pub use arrangement_config::ArrangementConfig;
+pub use frame_buffer::{Dither, FrameBufferConfig, ToneMap};
 pub use light_arrangement::LightArrangement;
+pub use topology::Topology;
@@ -0,0 +1,198 @@
+use std::ops::Range;
+
+use super::arrangement_config::ArrangementConfig;
+use crate::math::distance;
+
+/// How the lights are wired together: each light is connected to the next index, except where the
+/// strip is broken into separate segments
+/// Distances along the wire are measured in the same coordinates as the light locations, so
+/// effects that follow the wire move at the same speed through space everywhere
+#[derive(Debug, Clone, PartialEq)]
+pub struct Topology<const N: usize> {
+    /// Location of each index, `None` for indices that are not in the arrangement
+    locations: Vec<Option<[f64; N]>>,
+    /// `connected[i]` is true if light `i` is wired to light `i + 1`
+    connected: Vec<bool>,
+    /// Distance along the wire from light 0 to each light
+    arc_lengths: Vec<f64>,
+}
+
+impl<const N: usize> Topology<N> {
+    /// Creates a topology with every light in `config` connected to the next index
+    /// Indices missing from `config` break the wire
+    pub fn new(config: &ArrangementConfig<N>) -> Self {
+        let size = config
+            .light_locations
+            .iter()
+            .map(|(_, index)| index + 1)
+            .max()
+            .unwrap_or(0);
+        let mut locations = vec![None; size];
+        for (loc, index) in config.light_locations.iter() {
+            locations[*index] = Some(*loc);
+        }
+        Topology {
+            connected: vec![true; size.saturating_sub(1)],
+            arc_lengths: vec![],
+            locations,
+        }
+        .with_breaks(&[])
+    }
+
+    /// Returns this topology with the wire broken after each index in `breaks`
+    pub fn with_breaks(mut self, breaks: &[usize]) -> Self {
+        self.set_breaks(breaks);
+        self
+    }
+
+    /// Breaks the wire after each index in `breaks`, so light `i` and `i + 1` are not connected,
+    /// replacing any breaks set before. Indices missing from the arrangement stay broken
+    pub fn set_breaks(&mut self, breaks: &[usize]) {
+        for (index, connected) in self.connected.iter_mut().enumerate() {
+            *connected = !breaks.contains(&index)
+                && self.locations[index].is_some()
+                && self.locations[index + 1].is_some();
+        }
+        self.measure();
+    }
+
+    /// True if light `index` is wired to light `index + 1`
+    pub fn is_connected(&self, index: usize) -> bool {
+        self.connected.get(index).copied().unwrap_or(false)
+    }
+
+    /// Returns the range of indices in the segment of wire holding `index`
+    pub fn segment(&self, index: usize) -> Option<Range<usize>> {
+        self.locations.get(index)?.as_ref()?;
+        let mut start = index;
+        while start > 0 && self.is_connected(start - 1) {
+            start -= 1;
+        }
+        let mut end = index;
+        while self.is_connected(end) {
+            end += 1;
+        }
+        Some(start..end + 1)
+    }
+
+    /// Returns the ranges of indices in every connected segment of wire, in order
+    pub fn segments(&self) -> Vec<Range<usize>> {
+        let mut segments = vec![];
+        let mut index = 0;
+        while index < self.locations.len() {
+            match self.segment(index) {
+                Some(segment) => {
+                    index = segment.end;
+                    segments.push(segment);
+                }
+                None => index += 1,
+            }
+        }
+        segments
+    }
+
+    /// Distance along the wire from light 0 to light `index`, counting only connected parts
+    /// Lights on either side of a break are at the same arc length
+    pub fn arc_length(&self, index: usize) -> Option<f64> {
+        self.locations.get(index)?.as_ref()?;
+        Some(self.arc_lengths[index])
+    }
+
+    /// Total length of wire between lights
+    pub fn length(&self) -> f64 {
+        self.arc_lengths.last().copied().unwrap_or(0.0)
+    }
+
+    /// Distance along the wire between lights `a` and `b`, or `None` if they are not on the same
+    /// segment
+    pub fn along_strip_distance(&self, a: usize, b: usize) -> Option<f64> {
+        if !self.segment(a)?.contains(&b) {
+            return None;
+        }
+        Some((self.arc_lengths[a] - self.arc_lengths[b]).abs())
+    }
+
+    /// Returns the index and arc length of every light in the arrangement
+    pub(crate) fn arc_lengths(&self) -> impl Iterator<Item = (usize, f64)> + '_ {
+        self.arc_lengths
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| self.locations[*index].is_some())
+            .map(|(index, length)| (index, *length))
+    }
+
+    fn measure(&mut self) {
+        let mut length = 0.0;
+        self.arc_lengths = Vec::with_capacity(self.locations.len());
+        for index in 0..self.locations.len() {
+            if index > 0 && self.connected[index - 1] {
+                if let (Some(a), Some(b)) = (self.locations[index - 1], self.locations[index]) {
+                    length += distance(&a, &b);
+                }
+            }
+            self.arc_lengths.push(length);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn make_topology() -> Topology<2> {
+        // an L of 3 lights across and 2 up, then a gap at index 5 and two more lights
+        Topology::new(&ArrangementConfig {
+            light_locations: vec![
+                ([0.0, 0.0], 0),
+                ([0.1, 0.0], 1),
+                ([0.2, 0.0], 2),
+                ([0.2, 0.1], 3),
+                ([0.2, 0.3], 4),
+                ([0.5, 0.5], 6),
+                ([0.5, 0.6], 7),
+            ],
+            number_children_for_division: 1,
+        })
+    }
+
+    #[test]
+    fn arc_lengths() {
+        let topology = make_topology();
+        let expected = [0.0, 0.1, 0.2, 0.3, 0.5];
+        for (index, length) in expected.iter().enumerate() {
+            assert!((topology.arc_length(index).unwrap() - length).abs() < 0.0001);
+        }
+        assert_eq!(topology.arc_length(5), None);
+        assert!((topology.arc_length(6).unwrap() - 0.5).abs() < 0.0001);
+        assert!((topology.length() - 0.6).abs() < 0.0001);
+        assert_eq!(topology.arc_lengths().count(), 7);
+    }
+
+    #[test]
+    fn segments() {
+        let topology = make_topology();
+        assert_eq!(topology.segments(), vec![0..5, 6..8]);
+        assert_eq!(topology.segment(3), Some(0..5));
+        assert_eq!(topology.segment(5), None);
+        assert!(topology.is_connected(3));
+        assert!(!topology.is_connected(4));
+        assert!(!topology.is_connected(7));
+
+        let topology = topology.with_breaks(&[2]);
+        assert_eq!(topology.segments(), vec![0..3, 3..5, 6..8]);
+        assert!((topology.arc_length(4).unwrap() - 0.4).abs() < 0.0001);
+
+        let topology = topology.with_breaks(&[]);
+        assert_eq!(topology.segments(), vec![0..5, 6..8]);
+    }
+
+    #[test]
+    fn along_strip_distance() {
+        let topology = make_topology().with_breaks(&[3]);
+        assert!((topology.along_strip_distance(0, 3).unwrap() - 0.3).abs() < 0.0001);
+        assert!((topology.along_strip_distance(3, 1).unwrap() - 0.2).abs() < 0.0001);
+        assert_eq!(topology.along_strip_distance(3, 4), None);
+        assert_eq!(topology.along_strip_distance(4, 6), None);
+        assert_eq!(topology.along_strip_distance(5, 5), None);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::color::Color;
+
+/// Ways to combine a new color with the color a light already has
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BlendMode {
+    /// Overwrite the current color
+    Replace,
+    /// Keep the brighter value of each channel
+    Max,
+    /// Add each channel, saturating at full brightness
+    Add,
+    /// Multiply each channel, so the result is never brighter than either color
+    Multiply,
+    /// Inverse of multiplying the inverses, so the result is never darker than either color
+    Screen,
+    /// Average of both colors
+    Average,
+    /// Paint the new color over the current one with an opacity in 0..1
+    AlphaOver(f64),
+}
+
+impl BlendMode {
+    /// Returns the result of blending `src` onto `dst`
+    pub fn blend(&self, dst: &Color, src: &Color) -> Color {
+        let (dr, dg, db) = dst.unit_components();
+        let (sr, sg, sb) = src.unit_components();
+        let [r, g, b] = self.blend_components([dr, dg, db], [sr, sg, sb]);
+        Color::from_unit(r, g, b)
+    }
+
+    /// Returns `dst` moved `intensity` in 0..1 of the way to the result of blending `src` onto it,
+    /// so a faded effect leaves a light closer to its current color whatever the mode
+    /// Like `Color::dim`, channels are truncated
+    pub fn blend_with_intensity(&self, dst: &Color, src: &Color, intensity: f64) -> Color {
+        let blended = self.blend(dst, src);
+        let intensity = intensity.clamp(0.0, 1.0);
+        let lerp = |d: u8, b: u8| (d as f64 + (b as f64 - d as f64) * intensity) as u8;
+        Color::rgb(
+            lerp(dst.red, blended.red),
+            lerp(dst.green, blended.green),
+            lerp(dst.blue, blended.blue),
+        )
+    }
+
+    /// Blends `src` onto `dst`, where both are (r, g, b) with 1.0 as full brightness
+    /// Results are not clamped, so values above 1.0 are kept; `Screen` treats them as 1.0
+    pub(crate) fn blend_components(&self, dst: [f64; 3], src: [f64; 3]) -> [f64; 3] {
+        let mut out = [0.0; 3];
+        for i in 0..3 {
+            let (d, s) = (dst[i], src[i]);
+            out[i] = match self {
+                BlendMode::Replace => s,
+                BlendMode::Max => d.max(s),
+                BlendMode::Add => d + s,
+                BlendMode::Multiply => d * s,
+                BlendMode::Screen => 1.0 - (1.0 - d.min(1.0)) * (1.0 - s.min(1.0)),
+                BlendMode::Average => (d + s) / 2.0,
+                BlendMode::AlphaOver(alpha) => {
+                    let alpha = alpha.clamp(0.0, 1.0);
+                    d * (1.0 - alpha) + s * alpha
+                }
+            };
+        }
+        out
+    }
+
+    /// Blends `src` onto `dst` like `blend_components`, then moves `dst` only `intensity` of the
+    /// way to the result
+    pub(crate) fn blend_components_with_intensity(
+        &self,
+        dst: [f64; 3],
+        src: [f64; 3],
+        intensity: f64,
+    ) -> [f64; 3] {
+        let blended = self.blend_components(dst, src);
+        let intensity = intensity.clamp(0.0, 1.0);
+        std::array::from_fn(|i| dst[i] + (blended[i] - dst[i]) * intensity)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn blend_modes() {
+        let dst = Color::rgb(200, 100, 0);
+        let src = Color::rgb(100, 200, 255);
+
+        assert_eq!(BlendMode::Replace.blend(&dst, &src), src);
+        assert_eq!(BlendMode::Max.blend(&dst, &src), Color::rgb(200, 200, 255));
+        assert_eq!(BlendMode::Add.blend(&dst, &src), Color::rgb(255, 255, 255));
+        assert_eq!(
+            BlendMode::Add.blend(&Color::rgb(10, 20, 30), &Color::rgb(1, 2, 3)),
+            Color::rgb(11, 22, 33)
+        );
+        assert_eq!(BlendMode::Multiply.blend(&dst, &src), Color::rgb(78, 78, 0));
+        assert_eq!(
+            BlendMode::Screen.blend(&dst, &src),
+            Color::rgb(222, 222, 255)
+        );
+        assert_eq!(
+            BlendMode::Average.blend(&dst, &src),
+            Color::rgb(150, 150, 128)
+        );
+        assert_eq!(BlendMode::AlphaOver(0.0).blend(&dst, &src), dst);
+        assert_eq!(BlendMode::AlphaOver(1.0).blend(&dst, &src), src);
+        assert_eq!(
+            BlendMode::AlphaOver(0.25).blend(&dst, &src),
+            Color::rgb(175, 125, 64)
+        );
+    }
+
+    #[test]
+    fn blend_identities() {
+        let color = Color::rgb(31, 130, 250);
+        let black = Color::rgb(0, 0, 0);
+        let white = Color::rgb(255, 255, 255);
+
+        assert_eq!(BlendMode::Max.blend(&color, &black), color);
+        assert_eq!(BlendMode::Add.blend(&color, &black), color);
+        assert_eq!(BlendMode::Multiply.blend(&color, &white), color);
+        assert_eq!(BlendMode::Multiply.blend(&color, &black), black);
+        assert_eq!(BlendMode::Screen.blend(&color, &black), color);
+        assert_eq!(BlendMode::Screen.blend(&color, &white), white);
+        assert_eq!(BlendMode::Average.blend(&color, &color), color);
+    }
+
+    #[test]
+    fn blend_with_intensity() {
+        let dst = Color::rgb(200, 100, 0);
+        let src = Color::rgb(100, 200, 255);
+        let white = Color::rgb(255, 255, 255);
+
+        for mode in [
+            BlendMode::Replace,
+            BlendMode::Multiply,
+            BlendMode::Average,
+            BlendMode::AlphaOver(0.5),
+        ] {
+            assert_eq!(mode.blend_with_intensity(&dst, &src, 0.0), dst);
+            assert_eq!(
+                mode.blend_with_intensity(&dst, &src, 1.0),
+                mode.blend(&dst, &src)
+            );
+        }
+        assert_eq!(
+            BlendMode::Replace.blend_with_intensity(&dst, &src, 0.5),
+            Color::rgb(150, 150, 127)
+        );
+        // a faded multiply by white leaves the color alone instead of darkening it
+        assert_eq!(
+            BlendMode::Multiply.blend_with_intensity(&dst, &white, 0.25),
+            dst
+        );
+    }
+}
@@ -1,5 +1,8 @@
 use std::cmp::max;
-use std::fmt::Debug;
+use std::fmt::{self, Debug, Display};
+use std::str::FromStr;
+
+use crate::LightArrangementError;
 
 /// Representing Colors to assign to lights in the Light Strip
 
@@ -16,6 +19,118 @@ impl Color {
         Color { red, green, blue }
     }
 
+    /// Creates a color from hue, saturation and value
+    /// `hue` is in degrees and wraps around, so 360.0 and 0.0 are both red
+    /// `saturation` and `value` are 0..1 and are clamped to that range
+    pub fn hsv(hue: f64, saturation: f64, value: f64) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let value = value.clamp(0.0, 1.0);
+        let chroma = value * saturation;
+        let (r, g, b) = hue_to_rgb(hue, chroma);
+        let m = value - chroma;
+        Color::from_unit(r + m, g + m, b + m)
+    }
+
+    /// Creates a color from hue, saturation and lightness
+    /// `hue` is in degrees and wraps around, so 360.0 and 0.0 are both red
+    /// `saturation` and `lightness` are 0..1 and are clamped to that range
+    pub fn hsl(hue: f64, saturation: f64, lightness: f64) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let lightness = lightness.clamp(0.0, 1.0);
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        let (r, g, b) = hue_to_rgb(hue, chroma);
+        let m = lightness - chroma / 2.0;
+        Color::from_unit(r + m, g + m, b + m)
+    }
+
+    /// Creates the color of a black body radiator at `kelvin`, which is how color temperatures
+    /// of white light are described. Around 2700K is a warm incandescent white, 6600K is close to
+    /// pure white and higher temperatures turn blue. Accurate from about 1000K to 12000K
+    pub fn from_kelvin(kelvin: f64) -> Self {
+        Color::tinted_white(kelvin, 1.0)
+    }
+
+    /// Creates a white with the color temperature `kelvin` at `brightness` in 0..1
+    /// To make the same temperature look alike on strips from different batches, set up each
+    /// strip with `ColorCorrection::with_white_point`
+    pub fn tinted_white(kelvin: f64, brightness: f64) -> Self {
+        let (r, g, b) = kelvin_to_rgb(kelvin);
+        let brightness = brightness.clamp(0.0, 1.0);
+        Color::from_unit(r * brightness, g * brightness, b * brightness)
+    }
+
+    /// Estimates the color temperature in kelvin of this color, treating it as a white light
+    /// Only the balance between the channels is considered, not brightness, and the result is
+    /// within 1000K..12000K. Returns `None` for black
+    pub fn estimate_kelvin(&self) -> Option<f64> {
+        let (r, g, b) = self.unit_components();
+        if r + g + b == 0.0 {
+            return None;
+        }
+        let target = (g + b) / (r + g + b);
+
+        // The share of green and blue only grows with temperature, so binary search for it
+        let (mut low, mut high) = (1000.0_f64, 12000.0_f64);
+        for _ in 0..50 {
+            let mid = (low + high) / 2.0;
+            let (r, g, b) = kelvin_to_rgb(mid);
+            if (g + b) / (r + g + b) < target {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        Some((low + high) / 2.0)
+    }
+
+    /// Returns this color as a (hue, saturation, value) tuple
+    /// Hue is in degrees 0..360, saturation and value are 0..1
+    /// Grays have a hue and saturation of 0
+    pub fn to_hsv(&self) -> (f64, f64, f64) {
+        let (r, g, b) = self.unit_components();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+        let saturation = if max == 0.0 { 0.0 } else { chroma / max };
+        (rgb_to_hue(r, g, b, max, chroma), saturation, max)
+    }
+
+    /// Returns this color as a (hue, saturation, lightness) tuple
+    /// Hue is in degrees 0..360, saturation and lightness are 0..1
+    /// Grays have a hue and saturation of 0
+    pub fn to_hsl(&self) -> (f64, f64, f64) {
+        let (r, g, b) = self.unit_components();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+        let lightness = (max + min) / 2.0;
+        let saturation = if chroma == 0.0 {
+            0.0
+        } else {
+            chroma / (1.0 - (2.0 * lightness - 1.0).abs())
+        };
+        (rgb_to_hue(r, g, b, max, chroma), saturation, lightness)
+    }
+
+    /// Rotates the hue of this color by `degrees`, keeping its saturation and value
+    /// Since colors are stored as 8 bit channels, animations should keep their own hue and
+    /// recreate the color with `Color::hsv` instead of shifting the same color repeatedly
+    pub fn shift_hue(&mut self, degrees: f64) {
+        let (hue, saturation, value) = self.to_hsv();
+        *self = Color::hsv(hue + degrees, saturation, value);
+    }
+
+    /// Increases the saturation (in HSV) of this color by `amount`, clamping at fully saturated
+    pub fn saturate(&mut self, amount: f64) {
+        let (hue, saturation, value) = self.to_hsv();
+        *self = Color::hsv(hue, saturation + amount, value);
+    }
+
+    /// Decreases the saturation (in HSV) of this color by `amount`, clamping at gray
+    pub fn desaturate(&mut self, amount: f64) {
+        self.saturate(-amount);
+    }
+
     /// Dim this color so its brightness is `amount` percentage of what it was
     #[inline]
     pub fn dim(&mut self, amount: f64) {
@@ -33,6 +148,83 @@ impl Color {
         return (r, g, b);
     }
 
+    /// Creates a color from components in 0..1, rounding to the nearest 8 bit value
+    #[inline]
+    pub(crate) fn from_unit(red: f64, green: f64, blue: f64) -> Self {
+        Color {
+            red: unit_to_u8(red),
+            green: unit_to_u8(green),
+            blue: unit_to_u8(blue),
+        }
+    }
+
+    /// Returns this color's components as a (r, g, b) tuple of f64 with values from 0..1
+    #[inline]
+    pub(crate) fn unit_components(&self) -> (f64, f64, f64) {
+        (
+            self.red as f64 / 255.0,
+            self.green as f64 / 255.0,
+            self.blue as f64 / 255.0,
+        )
+    }
+
+    /// Returns the color `t` of the way from this color to `other`, interpolating in `space`
+    /// `t` is clamped to 0..1, where 0 is this color and 1 is `other`
+    pub fn lerp(&self, other: &Color, t: f64, space: Interpolation) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let (r1, g1, b1) = self.unit_components();
+        let (r2, g2, b2) = other.unit_components();
+        match space {
+            Interpolation::Srgb => {
+                Color::from_unit(lerp(r1, r2, t), lerp(g1, g2, t), lerp(b1, b2, t))
+            }
+            Interpolation::LinearRgb => Color::from_unit(
+                linear_to_srgb(lerp(srgb_to_linear(r1), srgb_to_linear(r2), t)),
+                linear_to_srgb(lerp(srgb_to_linear(g1), srgb_to_linear(g2), t)),
+                linear_to_srgb(lerp(srgb_to_linear(b1), srgb_to_linear(b2), t)),
+            ),
+            Interpolation::Hsv => {
+                let (mut h1, mut s1, v1) = self.to_hsv();
+                let (mut h2, mut s2, v2) = other.to_hsv();
+                // A gray has no meaningful hue and black has no meaningful saturation either, so
+                // take those from the other color
+                if s1 == 0.0 {
+                    h1 = h2;
+                } else if s2 == 0.0 {
+                    h2 = h1;
+                }
+                if v1 == 0.0 {
+                    s1 = s2;
+                } else if v2 == 0.0 {
+                    s2 = s1;
+                }
+                let mut delta = (h2 - h1).rem_euclid(360.0);
+                if delta > 180.0 {
+                    delta -= 360.0;
+                }
+                Color::hsv(h1 + delta * t, lerp(s1, s2, t), lerp(v1, v2, t))
+            }
+            Interpolation::Oklab => {
+                let lab1 = linear_srgb_to_oklab(
+                    srgb_to_linear(r1),
+                    srgb_to_linear(g1),
+                    srgb_to_linear(b1),
+                );
+                let lab2 = linear_srgb_to_oklab(
+                    srgb_to_linear(r2),
+                    srgb_to_linear(g2),
+                    srgb_to_linear(b2),
+                );
+                let (r, g, b) = oklab_to_linear_srgb(
+                    lerp(lab1.0, lab2.0, t),
+                    lerp(lab1.1, lab2.1, t),
+                    lerp(lab1.2, lab2.2, t),
+                );
+                Color::from_unit(linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b))
+            }
+        }
+    }
+
     /// Merges this color with `other`
     #[inline]
     pub fn merge(&mut self, other: Color) {
@@ -51,3 +243,911 @@ impl Debug for Color {
             .finish()
     }
 }
+
+impl Display for Color {
+    /// Formats the color as a hex string such as `#ff8000`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+}
+
+impl FromStr for Color {
+    type Err = LightArrangementError;
+
+    /// Parses colors written the way CSS does:
+    /// `#rrggbb` or `#rgb` hex, named colors such as `orange`, `rgb(255, 128, 0)` with values
+    /// 0..255 or percentages, and `hsl(30, 100%, 50%)` with the hue in degrees
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.trim().to_ascii_lowercase();
+        let error = |reason: &str| {
+            LightArrangementError::new(format!("Unable to parse color \"{}\": {}", s, reason))
+        };
+
+        if let Some(hex) = text.strip_prefix('#') {
+            return parse_hex(hex).ok_or_else(|| {
+                error("hex colors should be #rgb or #rrggbb with digits 0-9 and a-f")
+            });
+        }
+
+        if let Some((function, args)) = text.strip_suffix(')').and_then(|rest| rest.split_once('('))
+        {
+            let args: Vec<&str> = args
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|arg| !arg.is_empty())
+                .collect();
+            if args.len() != 3 {
+                return Err(error(&format!(
+                    "expected 3 values in {}(), found {}",
+                    function.trim(),
+                    args.len()
+                )));
+            }
+            return match function.trim() {
+                "rgb" => {
+                    let mut channels = [0.0; 3];
+                    for (channel, arg) in channels.iter_mut().zip(args) {
+                        *channel = parse_rgb_channel(arg).ok_or_else(|| {
+                            error(&format!(
+                                "\"{}\" is not a number 0..255 or a percentage",
+                                arg
+                            ))
+                        })?;
+                    }
+                    Ok(Color::from_unit(channels[0], channels[1], channels[2]))
+                }
+                "hsl" => {
+                    let hue = parse_hue(args[0]).ok_or_else(|| {
+                        error(&format!("\"{}\" is not a hue in degrees", args[0]))
+                    })?;
+                    let saturation = parse_percentage(args[1])
+                        .ok_or_else(|| error(&format!("\"{}\" is not a percentage", args[1])))?;
+                    let lightness = parse_percentage(args[2])
+                        .ok_or_else(|| error(&format!("\"{}\" is not a percentage", args[2])))?;
+                    Ok(Color::hsl(hue, saturation, lightness))
+                }
+                other => Err(error(&format!(
+                    "unknown function \"{}\", expected rgb() or hsl()",
+                    other
+                ))),
+            };
+        }
+
+        CSS_COLORS
+            .binary_search_by(|(name, _)| (*name).cmp(text.as_str()))
+            .map(|i| {
+                let value = CSS_COLORS[i].1;
+                Color::rgb((value >> 16) as u8, (value >> 8) as u8, value as u8)
+            })
+            .map_err(|_| error("not a hex color, rgb(), hsl() or a CSS color name"))
+    }
+}
+
+/// Color for strips with a dedicated white LED next to the red, green and blue ones, like SK6812
+/// RGBW strips
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ColorW {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub white: u8,
+}
+
+impl ColorW {
+    #[inline]
+    pub fn rgbw(red: u8, green: u8, blue: u8, white: u8) -> Self {
+        ColorW {
+            red,
+            green,
+            blue,
+            white,
+        }
+    }
+
+    /// Splits `color` into RGB and white parts using `extraction`
+    pub fn from_color(color: &Color, extraction: WhiteExtraction) -> Self {
+        let white_rgb = match extraction.white_point() {
+            Some(white_rgb) => white_rgb,
+            None => return ColorW::rgbw(color.red, color.green, color.blue, 0),
+        };
+
+        // Largest amount of the white LED that fits under every channel of `color`
+        let (r, g, b) = color.unit_components();
+        let white = [r, g, b]
+            .iter()
+            .zip(white_rgb.iter())
+            .filter(|(_, w)| **w > 0.0)
+            .map(|(c, w)| c / w)
+            .fold(1.0_f64, f64::min);
+
+        ColorW {
+            red: unit_to_u8(r - white * white_rgb[0]),
+            green: unit_to_u8(g - white * white_rgb[1]),
+            blue: unit_to_u8(b - white * white_rgb[2]),
+            white: unit_to_u8(white),
+        }
+    }
+
+    /// Returns the RGB color that looks the same as this one, assuming the white LED was
+    /// extracted with `extraction`. Channels saturate at 255
+    pub fn to_color(&self, extraction: WhiteExtraction) -> Color {
+        let white_rgb = extraction.white_point().unwrap_or([1.0; 3]);
+        let white = self.white as f64 / 255.0;
+        Color::from_unit(
+            self.red as f64 / 255.0 + white * white_rgb[0],
+            self.green as f64 / 255.0 + white * white_rgb[1],
+            self.blue as f64 / 255.0 + white * white_rgb[2],
+        )
+    }
+}
+
+/// Color with an alpha channel for painting translucent colors over what a light already shows
+/// An alpha of 255 is fully opaque and 0 is fully transparent
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ColorA {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub alpha: u8,
+}
+
+impl ColorA {
+    #[inline]
+    pub fn rgba(red: u8, green: u8, blue: u8, alpha: u8) -> Self {
+        ColorA {
+            red,
+            green,
+            blue,
+            alpha,
+        }
+    }
+
+    /// Creates a translucent version of `color` with `opacity` in 0..1
+    pub fn from_color(color: &Color, opacity: f64) -> Self {
+        ColorA::rgba(color.red, color.green, color.blue, unit_to_u8(opacity))
+    }
+
+    /// Returns the color without its alpha
+    #[inline]
+    pub fn color(&self) -> Color {
+        Color::rgb(self.red, self.green, self.blue)
+    }
+
+    /// Returns the alpha as an opacity in 0..1
+    #[inline]
+    pub fn opacity(&self) -> f64 {
+        self.alpha as f64 / 255.0
+    }
+
+    /// Composites this color over `dst` using Porter-Duff "over"
+    pub fn over(&self, dst: &ColorA) -> ColorA {
+        let src_alpha = self.opacity();
+        let dst_alpha = dst.opacity() * (1.0 - src_alpha);
+        let out_alpha = src_alpha + dst_alpha;
+        if out_alpha == 0.0 {
+            return ColorA::rgba(0, 0, 0, 0);
+        }
+
+        let (sr, sg, sb) = self.color().unit_components();
+        let (dr, dg, db) = dst.color().unit_components();
+        let mix = |s: f64, d: f64| (s * src_alpha + d * dst_alpha) / out_alpha;
+        let color = Color::from_unit(mix(sr, dr), mix(sg, dg), mix(sb, db));
+        ColorA::from_color(&color, out_alpha)
+    }
+
+    /// Composites this color over the opaque color `dst`, like a light that is already showing it
+    pub fn over_color(&self, dst: &Color) -> Color {
+        self.over(&ColorA::from_color(dst, 1.0)).color()
+    }
+}
+
+/// How the white channel of an RGBW strip is derived from an RGB `Color`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum WhiteExtraction {
+    /// Never use the white LED
+    None,
+    /// Move the gray part of the color, the smallest of the red, green and blue values, to the
+    /// white LED. Assumes the white LED matches full RGB white
+    MinSubtraction,
+    /// Like `MinSubtraction`, but the white LED is taken to be a light of this color temperature
+    /// in kelvin, so the leftover RGB makes up for its tint. SK6812 strips are sold in around
+    /// 2700K (warm white), 4500K (natural white) and 6500K (cool white)
+    ColorTemperature(f64),
+}
+
+impl WhiteExtraction {
+    /// Color of the white LED in 0..1 RGB, or `None` if the white LED is not used
+    fn white_point(&self) -> Option<[f64; 3]> {
+        match self {
+            WhiteExtraction::None => None,
+            WhiteExtraction::MinSubtraction => Some([1.0; 3]),
+            WhiteExtraction::ColorTemperature(kelvin) => {
+                let (r, g, b) = kelvin_to_rgb(*kelvin);
+                Some([r, g, b])
+            }
+        }
+    }
+}
+
+/// Color spaces `Color::lerp` can interpolate in
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Interpolation {
+    /// Straight interpolation of the stored 8 bit values. Cheap, but fades look dark and muddy
+    Srgb,
+    /// Interpolation of physical light intensity, which is how two LEDs mixing would look
+    LinearRgb,
+    /// Interpolation of hue, saturation and value, taking the shortest way around the hue circle
+    Hsv,
+    /// Interpolation in the perceptually uniform Oklab space, giving even looking fades
+    Oklab,
+}
+
+#[inline]
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+/// Converts an sRGB encoded component in 0..1 to linear light intensity
+#[inline]
+fn srgb_to_linear(value: f64) -> f64 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts a linear light intensity in 0..1 to an sRGB encoded component
+#[inline]
+fn linear_to_srgb(value: f64) -> f64 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Converts linear sRGB to Oklab (L, a, b)
+fn linear_srgb_to_oklab(r: f64, g: f64, b: f64) -> (f64, f64, f64) {
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+    (
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    )
+}
+
+/// Converts Oklab (L, a, b) to linear sRGB
+fn oklab_to_linear_srgb(l: f64, a: f64, b: f64) -> (f64, f64, f64) {
+    let l_ = (l + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+    let m_ = (l - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+    let s_ = (l - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+    (
+        4.0767416621 * l_ - 3.3077115913 * m_ + 0.2309699292 * s_,
+        -1.2684380046 * l_ + 2.6097574011 * m_ - 0.3413193965 * s_,
+        -0.0041960863 * l_ - 0.7034186147 * m_ + 1.7076147010 * s_,
+    )
+}
+
+/// Approximates the (r, g, b) in 0..1 of a black body radiator at `kelvin`, such as an
+/// incandescent bulb or daylight. Accurate from about 1000K to 40000K; 6600K is close to white
+pub(crate) fn kelvin_to_rgb(kelvin: f64) -> (f64, f64, f64) {
+    let temp = kelvin.clamp(1000.0, 40000.0) / 100.0;
+
+    let red = if temp <= 66.0 {
+        255.0
+    } else {
+        329.698727446 * (temp - 60.0).powf(-0.1332047592)
+    };
+    let green = if temp <= 66.0 {
+        99.4708025861 * temp.ln() - 161.1195681661
+    } else {
+        288.1221695283 * (temp - 60.0).powf(-0.0755148492)
+    };
+    let blue = if temp >= 66.0 {
+        255.0
+    } else if temp <= 19.0 {
+        0.0
+    } else {
+        138.5177312231 * (temp - 10.0).ln() - 305.0447927307
+    };
+
+    (
+        (red / 255.0).clamp(0.0, 1.0),
+        (green / 255.0).clamp(0.0, 1.0),
+        (blue / 255.0).clamp(0.0, 1.0),
+    )
+}
+
+/// Parses the digits of a `#rgb` or `#rrggbb` color
+fn parse_hex(hex: &str) -> Option<Color> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digit = |i: usize| u8::from_str_radix(&hex[i..=i], 16).ok();
+    let pair = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    match hex.len() {
+        3 => Some(Color::rgb(digit(0)? * 17, digit(1)? * 17, digit(2)? * 17)),
+        6 => Some(Color::rgb(pair(0)?, pair(2)?, pair(4)?)),
+        _ => None,
+    }
+}
+
+/// Parses a `rgb()` channel, either 0..255 or a percentage, into 0..1
+fn parse_rgb_channel(arg: &str) -> Option<f64> {
+    if arg.ends_with('%') {
+        return parse_percentage(arg);
+    }
+    let value: f64 = arg.parse().ok()?;
+    (0.0..=255.0).contains(&value).then_some(value / 255.0)
+}
+
+/// Parses a percentage such as `50%` into 0..1. Like CSS, the `%` may be left off
+fn parse_percentage(arg: &str) -> Option<f64> {
+    let value: f64 = arg.strip_suffix('%').unwrap_or(arg).parse().ok()?;
+    (0.0..=100.0).contains(&value).then_some(value / 100.0)
+}
+
+/// Parses a hue in degrees, with an optional `deg` suffix
+fn parse_hue(arg: &str) -> Option<f64> {
+    let value: f64 = arg.strip_suffix("deg").unwrap_or(arg).parse().ok()?;
+    value.is_finite().then_some(value)
+}
+
+/// Converts a component in 0..1 to the nearest 8 bit value
+#[inline]
+pub(crate) fn unit_to_u8(value: f64) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Returns the (r, g, b) of a color with `hue` in degrees and `chroma`, before adding the
+/// lightness offset shared by HSV and HSL
+fn hue_to_rgb(hue: f64, chroma: f64) -> (f64, f64, f64) {
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    match sector as u8 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    }
+}
+
+/// Returns the hue in degrees 0..360 of the color with components `r`, `g`, `b`, where `max`
+/// is the largest component and `chroma` is the difference between the largest and smallest
+fn rgb_to_hue(r: f64, g: f64, b: f64, max: f64, chroma: f64) -> f64 {
+    if chroma == 0.0 {
+        return 0.0;
+    }
+    let sector = if max == r {
+        ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / chroma + 2.0
+    } else {
+        (r - g) / chroma + 4.0
+    };
+    (sector * 60.0).rem_euclid(360.0)
+}
+
+/// CSS named colors, sorted by name so they can be binary searched
+const CSS_COLORS: [(&str, u32); 148] = [
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn approx(a: f64, b: f64) -> bool {
+        (a - b).abs() < 0.001
+    }
+
+    #[test]
+    fn hsv_primaries() {
+        assert_eq!(Color::hsv(0.0, 1.0, 1.0), Color::rgb(255, 0, 0));
+        assert_eq!(Color::hsv(120.0, 1.0, 1.0), Color::rgb(0, 255, 0));
+        assert_eq!(Color::hsv(240.0, 1.0, 1.0), Color::rgb(0, 0, 255));
+        assert_eq!(Color::hsv(60.0, 1.0, 1.0), Color::rgb(255, 255, 0));
+        assert_eq!(Color::hsv(360.0, 1.0, 1.0), Color::rgb(255, 0, 0));
+        assert_eq!(Color::hsv(-120.0, 1.0, 1.0), Color::rgb(0, 0, 255));
+        assert_eq!(Color::hsv(200.0, 0.0, 0.5), Color::rgb(128, 128, 128));
+        assert_eq!(Color::hsv(200.0, 1.0, 0.0), Color::rgb(0, 0, 0));
+    }
+
+    #[test]
+    fn hsl_primaries() {
+        assert_eq!(Color::hsl(0.0, 1.0, 0.5), Color::rgb(255, 0, 0));
+        assert_eq!(Color::hsl(120.0, 1.0, 0.5), Color::rgb(0, 255, 0));
+        assert_eq!(Color::hsl(240.0, 1.0, 0.5), Color::rgb(0, 0, 255));
+        assert_eq!(Color::hsl(0.0, 1.0, 1.0), Color::rgb(255, 255, 255));
+        assert_eq!(Color::hsl(0.0, 1.0, 0.0), Color::rgb(0, 0, 0));
+        assert_eq!(Color::hsl(180.0, 1.0, 0.25), Color::rgb(0, 128, 128));
+    }
+
+    #[test]
+    fn to_hsv_and_hsl() {
+        let (h, s, v) = Color::rgb(0, 128, 128).to_hsv();
+        assert!(approx(h, 180.0) && approx(s, 1.0) && approx(v, 128.0 / 255.0));
+        let (h, s, l) = Color::rgb(0, 128, 128).to_hsl();
+        assert!(approx(h, 180.0) && approx(s, 1.0) && approx(l, 64.0 / 255.0));
+
+        let (h, s, v) = Color::rgb(255, 0, 255).to_hsv();
+        assert!(approx(h, 300.0) && approx(s, 1.0) && approx(v, 1.0));
+        let (h, s, v) = Color::rgb(40, 40, 40).to_hsv();
+        assert!(approx(h, 0.0) && approx(s, 0.0) && approx(v, 40.0 / 255.0));
+    }
+
+    #[test]
+    fn round_trips() {
+        for r in (0..=255).step_by(5) {
+            for g in (0..=255).step_by(3) {
+                for b in (0..=255).step_by(7) {
+                    let color = Color::rgb(r, g, b);
+                    let (h, s, v) = color.to_hsv();
+                    assert_eq!(Color::hsv(h, s, v), color);
+                    let (h, s, l) = color.to_hsl();
+                    assert_eq!(Color::hsl(h, s, l), color);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn shift_hue() {
+        let mut color = Color::rgb(255, 0, 0);
+        color.shift_hue(120.0);
+        assert_eq!(color, Color::rgb(0, 255, 0));
+        color.shift_hue(-240.0);
+        assert_eq!(color, Color::rgb(0, 0, 255));
+        color.shift_hue(720.0);
+        assert_eq!(color, Color::rgb(0, 0, 255));
+    }
+
+    #[test]
+    fn lerp_endpoints() {
+        let a = Color::rgb(255, 40, 0);
+        let b = Color::rgb(10, 90, 200);
+        for space in [
+            Interpolation::Srgb,
+            Interpolation::LinearRgb,
+            Interpolation::Hsv,
+            Interpolation::Oklab,
+        ] {
+            assert_eq!(a.lerp(&b, 0.0, space), a);
+            assert_eq!(a.lerp(&b, 1.0, space), b);
+            assert_eq!(a.lerp(&b, -1.0, space), a);
+            assert_eq!(a.lerp(&b, 2.0, space), b);
+            assert_eq!(a.lerp(&a, 0.5, space), a);
+        }
+    }
+
+    #[test]
+    fn lerp_srgb_and_linear() {
+        let black = Color::rgb(0, 0, 0);
+        let white = Color::rgb(255, 255, 255);
+        assert_eq!(
+            black.lerp(&white, 0.5, Interpolation::Srgb),
+            Color::rgb(128, 128, 128)
+        );
+        // Half the light of white is brighter than halfway in sRGB
+        assert_eq!(
+            black.lerp(&white, 0.5, Interpolation::LinearRgb),
+            Color::rgb(188, 188, 188)
+        );
+
+        let red = Color::rgb(255, 0, 0);
+        let green = Color::rgb(0, 255, 0);
+        assert_eq!(
+            red.lerp(&green, 0.5, Interpolation::LinearRgb),
+            Color::rgb(188, 188, 0)
+        );
+    }
+
+    #[test]
+    fn lerp_hsv_shortest_hue() {
+        let red = Color::hsv(350.0, 1.0, 1.0);
+        let orange = Color::hsv(30.0, 1.0, 1.0);
+        let (h, _, _) = red.lerp(&orange, 0.5, Interpolation::Hsv).to_hsv();
+        assert!((h - 10.0).abs() < 1.0);
+        let (h, _, _) = orange.lerp(&red, 0.5, Interpolation::Hsv).to_hsv();
+        assert!((h - 10.0).abs() < 1.0);
+
+        // fading from black keeps the hue of the other color
+        let black = Color::rgb(0, 0, 0);
+        let blue = Color::rgb(0, 0, 255);
+        assert_eq!(
+            black.lerp(&blue, 0.5, Interpolation::Hsv),
+            Color::rgb(0, 0, 128)
+        );
+    }
+
+    #[test]
+    fn lerp_oklab() {
+        // Oklab conversions round trip
+        for color in [
+            Color::rgb(255, 0, 0),
+            Color::rgb(12, 200, 99),
+            Color::rgb(255, 255, 255),
+            Color::rgb(0, 0, 0),
+        ] {
+            let (r, g, b) = color.unit_components();
+            let (l, a, b) =
+                linear_srgb_to_oklab(srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b));
+            let (r, g, b) = oklab_to_linear_srgb(l, a, b);
+            assert_eq!(
+                Color::from_unit(linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b)),
+                color
+            );
+        }
+
+        // Perceptual midpoint between black and white is a mid gray
+        let mid = Color::rgb(0, 0, 0).lerp(&Color::rgb(255, 255, 255), 0.5, Interpolation::Oklab);
+        assert_eq!(mid.red, mid.green);
+        assert_eq!(mid.green, mid.blue);
+        assert!(mid.red > 90 && mid.red < 120);
+    }
+
+    #[test]
+    fn white_extraction() {
+        let color = Color::rgb(200, 150, 100);
+        assert_eq!(
+            ColorW::from_color(&color, WhiteExtraction::None),
+            ColorW::rgbw(200, 150, 100, 0)
+        );
+        assert_eq!(
+            ColorW::from_color(&color, WhiteExtraction::MinSubtraction),
+            ColorW::rgbw(100, 50, 0, 100)
+        );
+        assert_eq!(
+            ColorW::from_color(&Color::rgb(255, 255, 255), WhiteExtraction::MinSubtraction),
+            ColorW::rgbw(0, 0, 0, 255)
+        );
+
+        // A warm white LED already gives off more red than blue, so less red is left over
+        let warm = ColorW::from_color(
+            &Color::rgb(255, 255, 255),
+            WhiteExtraction::ColorTemperature(2700.0),
+        );
+        assert_eq!(warm.white, 255);
+        assert_eq!(warm.red, 0);
+        assert!(warm.blue > warm.green);
+
+        for extraction in [
+            WhiteExtraction::None,
+            WhiteExtraction::MinSubtraction,
+            WhiteExtraction::ColorTemperature(4500.0),
+        ] {
+            for color in [
+                color,
+                Color::rgb(255, 255, 255),
+                Color::rgb(0, 0, 0),
+                Color::rgb(10, 255, 90),
+            ] {
+                let color_w = ColorW::from_color(&color, extraction);
+                let back = color_w.to_color(extraction);
+                assert!(back.red.abs_diff(color.red) <= 1);
+                assert!(back.green.abs_diff(color.green) <= 1);
+                assert!(back.blue.abs_diff(color.blue) <= 1);
+            }
+        }
+    }
+
+    #[test]
+    fn alpha_over() {
+        let red = ColorA::rgba(255, 0, 0, 255);
+        let blue = ColorA::rgba(0, 0, 255, 255);
+        let clear = ColorA::rgba(0, 0, 0, 0);
+
+        assert_eq!(red.over(&blue), red);
+        assert_eq!(clear.over(&blue), blue);
+        assert_eq!(red.over(&clear), red);
+        assert_eq!(clear.over(&clear), clear);
+
+        let half_red = ColorA::from_color(&Color::rgb(255, 0, 0), 0.5);
+        assert_eq!(half_red.alpha, 128);
+        let result = half_red.over(&blue);
+        assert_eq!(result.alpha, 255);
+        assert_eq!(result.color(), Color::rgb(128, 0, 127));
+
+        // Translucent over translucent stays translucent and keeps the hue of both
+        let result = half_red.over(&ColorA::rgba(0, 0, 255, 128));
+        assert_eq!(result.alpha, 192);
+        assert!(result.red > result.blue && result.blue > 0);
+
+        assert_eq!(
+            ColorA::from_color(&Color::rgb(200, 100, 0), 0.4).over_color(&Color::rgb(0, 100, 200)),
+            Color::rgb(80, 100, 120)
+        );
+    }
+
+    #[test]
+    fn kelvin() {
+        assert_eq!(Color::from_kelvin(6600.0), Color::rgb(255, 255, 255));
+        let candle = Color::from_kelvin(1000.0);
+        assert_eq!(candle.red, 255);
+        assert_eq!(candle.blue, 0);
+        let warm = Color::from_kelvin(2700.0);
+        assert!(warm.red == 255 && warm.green < 200 && warm.blue < warm.green);
+        let cool = Color::from_kelvin(12000.0);
+        assert!(cool.blue == 255 && cool.red < cool.green);
+
+        assert_eq!(Color::tinted_white(6600.0, 0.5), Color::rgb(128, 128, 128));
+        assert_eq!(Color::tinted_white(2700.0, 0.0), Color::rgb(0, 0, 0));
+        let dim_warm = Color::tinted_white(2700.0, 0.5);
+        assert!(dim_warm.red == 128 && dim_warm.green.abs_diff(warm.green / 2) <= 1);
+    }
+
+    #[test]
+    fn estimate_kelvin() {
+        assert_eq!(Color::rgb(0, 0, 0).estimate_kelvin(), None);
+        for kelvin in [1500.0, 2700.0, 4000.0, 5500.0, 8000.0, 11000.0] {
+            let estimate = Color::from_kelvin(kelvin).estimate_kelvin().unwrap();
+            assert!((estimate - kelvin).abs() / kelvin < 0.03);
+            let estimate = Color::tinted_white(kelvin, 0.6).estimate_kelvin().unwrap();
+            assert!((estimate - kelvin).abs() / kelvin < 0.03);
+        }
+        assert_eq!(
+            Color::rgb(255, 0, 0).estimate_kelvin().unwrap().round(),
+            1000.0
+        );
+        assert_eq!(
+            Color::rgb(0, 0, 255).estimate_kelvin().unwrap().round(),
+            12000.0
+        );
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Color::rgb(255, 128, 0).to_string(), "#ff8000");
+        assert_eq!(Color::rgb(0, 0, 0).to_string(), "#000000");
+        assert_eq!(Color::rgb(1, 2, 171).to_string(), "#0102ab");
+    }
+
+    #[test]
+    fn parse_hex_and_names() {
+        assert_eq!("#ff8000".parse::<Color>().unwrap(), Color::rgb(255, 128, 0));
+        assert_eq!("#FF8000".parse::<Color>().unwrap(), Color::rgb(255, 128, 0));
+        assert_eq!("#f80".parse::<Color>().unwrap(), Color::rgb(255, 136, 0));
+        assert_eq!("  #000  ".parse::<Color>().unwrap(), Color::rgb(0, 0, 0));
+
+        assert_eq!("red".parse::<Color>().unwrap(), Color::rgb(255, 0, 0));
+        assert_eq!(
+            "RebeccaPurple".parse::<Color>().unwrap(),
+            Color::rgb(102, 51, 153)
+        );
+        assert_eq!(
+            "aliceblue".parse::<Color>().unwrap(),
+            Color::rgb(240, 248, 255)
+        );
+        assert_eq!(
+            "yellowgreen".parse::<Color>().unwrap(),
+            Color::rgb(154, 205, 50)
+        );
+        assert!(CSS_COLORS.windows(2).all(|w| w[0].0 < w[1].0));
+
+        for color in [
+            Color::rgb(0, 0, 0),
+            Color::rgb(255, 255, 255),
+            Color::rgb(18, 52, 86),
+        ] {
+            assert_eq!(color.to_string().parse::<Color>().unwrap(), color);
+        }
+    }
+
+    #[test]
+    fn parse_functions() {
+        assert_eq!(
+            "rgb(255, 128, 0)".parse::<Color>().unwrap(),
+            Color::rgb(255, 128, 0)
+        );
+        assert_eq!(
+            "rgb(255 128 0)".parse::<Color>().unwrap(),
+            Color::rgb(255, 128, 0)
+        );
+        assert_eq!(
+            "RGB( 100%, 50%, 0% )".parse::<Color>().unwrap(),
+            Color::rgb(255, 128, 0)
+        );
+        assert_eq!(
+            "hsl(120, 100%, 50%)".parse::<Color>().unwrap(),
+            Color::rgb(0, 255, 0)
+        );
+        assert_eq!(
+            "hsl(240deg 100% 25%)".parse::<Color>().unwrap(),
+            Color::rgb(0, 0, 128)
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        for text in [
+            "",
+            "#12",
+            "#12345g",
+            "#1234",
+            "notacolor",
+            "rgb(1, 2)",
+            "rgb(1, 2, 3, 4)",
+            "rgb(256, 0, 0)",
+            "rgb(a, b, c)",
+            "rgb(-1, 0, 0)",
+            "hsl(0, 200%, 50%)",
+            "hsl(red, 50%, 50%)",
+            "cmyk(1, 2, 3)",
+            "rgb(1, 2, 3",
+        ] {
+            assert!(text.parse::<Color>().is_err(), "{} should not parse", text);
+        }
+        let err = "rgb(300, 0, 0)".parse::<Color>().unwrap_err();
+        assert!(err.reason().contains("rgb(300, 0, 0)"));
+        assert!(err.reason().contains("300"));
+    }
+
+    #[test]
+    fn saturate_and_desaturate() {
+        let mut color = Color::rgb(255, 0, 0);
+        color.desaturate(1.0);
+        assert_eq!(color, Color::rgb(255, 255, 255));
+
+        let mut color = Color::rgb(200, 100, 100);
+        color.saturate(2.0);
+        assert_eq!(color, Color::rgb(200, 0, 0));
+
+        let mut color = Color::hsv(90.0, 0.5, 1.0);
+        color.desaturate(0.25);
+        let (h, s, v) = color.to_hsv();
+        assert!((h - 90.0).abs() < 1.0 && (s - 0.25).abs() < 0.01 && approx(v, 1.0));
+    }
+}
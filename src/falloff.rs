@@ -0,0 +1,186 @@
+use std::{f64::consts::PI, fmt, sync::Arc};
+
+/// How much the Gaussian curve is squeezed, so the radius is this many standard deviations out
+const GAUSSIAN_DEVIATIONS: f64 = 3.0;
+
+/// How sharp the inverse square curve is, as the intensity at the center over the intensity at
+/// the radius before it is shifted down to reach 0 there
+const INVERSE_SQUARE_SHARPNESS: f64 = 16.0;
+
+/// Shapes of the curve lights fade out along, from full intensity at the center of an effect to
+/// nothing at its edge
+/// Curves are sampled with the distance from the center over the distance where the effect ends,
+/// and all but a hard `SoftStep` go from 1 at 0 to 0 at 1
+#[derive(Clone, Default)]
+pub enum Falloff {
+    /// Straight down, `1 - t`, which makes a cone
+    #[default]
+    Linear,
+    /// `(1 - t)²`, which drops off quickly and has a long dim tail
+    Quadratic,
+    /// `1 / (1 + k t²)`, shifted and scaled to reach 0 at the edge, which makes a bright point
+    /// with a soft glow around it like a point light
+    InverseSquare,
+    /// Bell curve with the edge three standard deviations out, shifted and scaled to reach 0
+    /// there
+    Gaussian,
+    /// Eases out of full intensity and into nothing, with no sharp change anywhere
+    Smoothstep,
+    /// Half a cosine wave, `(1 + cos(πt)) / 2`
+    Cosine,
+    /// Full intensity out to `1 - edge`, then easing out to nothing over the last `edge` of the
+    /// way. An `edge` of 0 is a hard edge
+    SoftStep(f64),
+    /// Any curve, called with `t` in 0..1. Results are clamped to 0..1
+    Custom(Arc<dyn Fn(f64) -> f64 + Send + Sync>),
+}
+
+impl Falloff {
+    /// Creates a custom falloff curve from `curve`
+    pub fn custom(curve: impl Fn(f64) -> f64 + Send + Sync + 'static) -> Self {
+        Falloff::Custom(Arc::new(curve))
+    }
+
+    /// Returns the intensity `t` of the way from the center of an effect to its edge, where `t`
+    /// is clamped to 0..1
+    pub fn intensity(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        let intensity = match self {
+            Falloff::Linear => 1.0 - t,
+            Falloff::Quadratic => (1.0 - t).powi(2),
+            Falloff::InverseSquare => {
+                let k = INVERSE_SQUARE_SHARPNESS - 1.0;
+                (INVERSE_SQUARE_SHARPNESS / (1.0 + k * t * t) - 1.0) / k
+            }
+            Falloff::Gaussian => {
+                let bell = |t: f64| (-(t * GAUSSIAN_DEVIATIONS).powi(2) / 2.0).exp();
+                (bell(t) - bell(1.0)) / (1.0 - bell(1.0))
+            }
+            Falloff::Smoothstep => 1.0 - smoothstep(t),
+            Falloff::Cosine => (1.0 + (PI * t).cos()) / 2.0,
+            Falloff::SoftStep(edge) => {
+                let edge = edge.clamp(0.0, 1.0);
+                if edge == 0.0 {
+                    1.0
+                } else {
+                    1.0 - smoothstep((t - (1.0 - edge)) / edge)
+                }
+            }
+            Falloff::Custom(curve) => curve(t),
+        };
+        intensity.clamp(0.0, 1.0)
+    }
+}
+
+impl fmt::Debug for Falloff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Falloff::Linear => write!(f, "Linear"),
+            Falloff::Quadratic => write!(f, "Quadratic"),
+            Falloff::InverseSquare => write!(f, "InverseSquare"),
+            Falloff::Gaussian => write!(f, "Gaussian"),
+            Falloff::Smoothstep => write!(f, "Smoothstep"),
+            Falloff::Cosine => write!(f, "Cosine"),
+            Falloff::SoftStep(edge) => f.debug_tuple("SoftStep").field(edge).finish(),
+            Falloff::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
+
+fn smoothstep(t: f64) -> f64 {
+    let t = t.clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn approx(a: f64, b: f64) -> bool {
+        (a - b).abs() < 0.0001
+    }
+
+    fn all() -> Vec<Falloff> {
+        vec![
+            Falloff::Linear,
+            Falloff::Quadratic,
+            Falloff::InverseSquare,
+            Falloff::Gaussian,
+            Falloff::Smoothstep,
+            Falloff::Cosine,
+            Falloff::SoftStep(0.5),
+            Falloff::custom(|t| 1.0 - t.sqrt()),
+        ]
+    }
+
+    #[test]
+    fn ends() {
+        for falloff in all() {
+            assert!(approx(falloff.intensity(0.0), 1.0), "{:?}", falloff);
+            assert!(approx(falloff.intensity(1.0), 0.0), "{:?}", falloff);
+            // clamped outside of 0..1
+            assert!(approx(falloff.intensity(-1.0), 1.0), "{:?}", falloff);
+            assert!(approx(falloff.intensity(2.0), 0.0), "{:?}", falloff);
+        }
+    }
+
+    #[test]
+    fn never_increases() {
+        for falloff in all() {
+            let samples: Vec<f64> = (0..=100)
+                .map(|i| falloff.intensity(i as f64 / 100.0))
+                .collect();
+            assert!(
+                samples.windows(2).all(|pair| pair[1] <= pair[0] + 1e-12),
+                "{:?}",
+                falloff
+            );
+        }
+    }
+
+    #[test]
+    fn shapes() {
+        assert!(approx(Falloff::Linear.intensity(0.25), 0.75));
+        assert!(approx(Falloff::Linear.intensity(0.5), 0.5));
+
+        assert!(approx(Falloff::Quadratic.intensity(0.5), 0.25));
+        assert!(approx(Falloff::Quadratic.intensity(0.9), 0.01));
+
+        // most of the light is right in the middle
+        let inverse_square = Falloff::InverseSquare;
+        assert!(approx(
+            inverse_square.intensity(0.5),
+            (16.0 / 4.75 - 1.0) / 15.0
+        ));
+        assert!(inverse_square.intensity(0.25) < 0.5);
+
+        // flat near the middle, then dropping faster than linear
+        let gaussian = Falloff::Gaussian;
+        assert!(gaussian.intensity(0.1) > 0.95);
+        assert!(gaussian.intensity(0.5) < 0.5);
+
+        // both ease in and out symmetrically
+        for falloff in [Falloff::Smoothstep, Falloff::Cosine] {
+            assert!(approx(falloff.intensity(0.5), 0.5));
+            assert!(approx(falloff.intensity(0.2), 1.0 - falloff.intensity(0.8)));
+            assert!(falloff.intensity(0.05) > 0.99);
+        }
+        assert!(approx(Falloff::Smoothstep.intensity(0.25), 1.0 - 0.15625));
+        assert!(approx(
+            Falloff::Cosine.intensity(0.25),
+            0.5 + 0.5_f64.sqrt() / 2.0
+        ));
+
+        let soft_step = Falloff::SoftStep(0.2);
+        assert_eq!(soft_step.intensity(0.5), 1.0);
+        assert_eq!(soft_step.intensity(0.8), 1.0);
+        assert!(approx(soft_step.intensity(0.9), 0.5));
+        assert_eq!(Falloff::SoftStep(0.0).intensity(0.99), 1.0);
+        assert!(approx(Falloff::SoftStep(1.0).intensity(0.5), 0.5));
+
+        let custom = Falloff::custom(|t| 2.0 - 4.0 * t);
+        assert_eq!(custom.intensity(0.1), 1.0);
+        assert!(approx(custom.intensity(0.4), 0.4));
+        assert_eq!(custom.intensity(0.6), 0.0);
+    }
+}
@@ -0,0 +1,284 @@
+use crate::color::{Color, Interpolation};
+use crate::LightArrangementError;
+
+/// Colors placed at positions along a line, which can be sampled anywhere in between
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    stops: Vec<(f64, Color)>,
+    interpolation: Interpolation,
+    mode: GradientMode,
+}
+
+/// How a `Gradient` is sampled outside of 0..1
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GradientMode {
+    /// Positions below 0 use the color at 0 and positions above 1 use the color at 1
+    Clamp,
+    /// Positions repeat every 1.0, so 1.25 samples the same color as 0.25
+    Wrap,
+    /// Positions bounce back and forth, so 1.25 samples the same color as 0.75
+    Mirror,
+}
+
+impl Gradient {
+    /// Creates a gradient from `stops`, which are (position, color) pairs
+    /// Positions are usually 0..1 and do not need to be sorted
+    /// The gradient interpolates in Oklab and clamps positions outside of 0..1 by default
+    pub fn new(stops: Vec<(f64, Color)>) -> Result<Self, LightArrangementError> {
+        if stops.is_empty() {
+            return Err(LightArrangementError::new(
+                "Gradient needs at least one color stop".to_string(),
+            ));
+        }
+        if stops.iter().any(|(pos, _)| !pos.is_finite()) {
+            return Err(LightArrangementError::new(
+                "Gradient stop positions must be finite".to_string(),
+            ));
+        }
+        let mut stops = stops;
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(Gradient {
+            stops,
+            interpolation: Interpolation::Oklab,
+            mode: GradientMode::Clamp,
+        })
+    }
+
+    /// Creates a gradient with `colors` spaced evenly from 0 to 1
+    pub fn evenly_spaced(colors: &[Color]) -> Result<Self, LightArrangementError> {
+        let last = colors.len().saturating_sub(1).max(1) as f64;
+        Gradient::new(
+            colors
+                .iter()
+                .enumerate()
+                .map(|(i, color)| (i as f64 / last, *color))
+                .collect(),
+        )
+    }
+
+    /// Returns this gradient interpolating between stops in `interpolation`
+    pub fn with_interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    /// Returns this gradient using `mode` for positions outside of 0..1
+    pub fn with_mode(mut self, mode: GradientMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn stops(&self) -> &[(f64, Color)] {
+        &self.stops
+    }
+
+    /// Returns the color of the gradient at position `t`
+    /// A NaN `t`, or an infinite one outside of `GradientMode::Clamp`, gives the first color
+    pub fn sample(&self, t: f64) -> Color {
+        let t = match self.mode {
+            GradientMode::Clamp => t,
+            GradientMode::Wrap => t.rem_euclid(1.0),
+            GradientMode::Mirror => 1.0 - (t.rem_euclid(2.0) - 1.0).abs(),
+        };
+
+        let first = self.stops[0];
+        let last = self.stops[self.stops.len() - 1];
+        if t.is_nan() || t <= first.0 {
+            return first.1;
+        }
+        if t >= last.0 {
+            return last.1;
+        }
+
+        // first stop strictly after `t`; exists and is not the first since first.0 < t < last.0
+        let upper = self.stops.partition_point(|(pos, _)| *pos <= t);
+        let (start_pos, start_color) = self.stops[upper - 1];
+        let (end_pos, end_color) = self.stops[upper];
+        let amount = (t - start_pos) / (end_pos - start_pos);
+        start_color.lerp(&end_color, amount, self.interpolation)
+    }
+
+    /// Full hue circle starting and ending at red, so it tiles seamlessly with `GradientMode::Wrap`
+    pub fn rainbow() -> Self {
+        Gradient::evenly_spaced(&[
+            Color::rgb(255, 0, 0),
+            Color::rgb(255, 255, 0),
+            Color::rgb(0, 255, 0),
+            Color::rgb(0, 255, 255),
+            Color::rgb(0, 0, 255),
+            Color::rgb(255, 0, 255),
+            Color::rgb(255, 0, 0),
+        ])
+        .expect("palette has stops")
+        .with_interpolation(Interpolation::Hsv)
+    }
+
+    /// Black through red and yellow to white, like heated metal
+    pub fn heat() -> Self {
+        Gradient::evenly_spaced(&[
+            Color::rgb(0, 0, 0),
+            Color::rgb(255, 0, 0),
+            Color::rgb(255, 255, 0),
+            Color::rgb(255, 255, 255),
+        ])
+        .expect("palette has stops")
+        .with_interpolation(Interpolation::LinearRgb)
+    }
+
+    /// Black through deep red and orange to yellow
+    pub fn fire() -> Self {
+        Gradient::new(vec![
+            (0.0, Color::rgb(0, 0, 0)),
+            (0.3, Color::rgb(128, 0, 0)),
+            (0.6, Color::rgb(255, 80, 0)),
+            (0.85, Color::rgb(255, 170, 0)),
+            (1.0, Color::rgb(255, 230, 80)),
+        ])
+        .expect("palette has stops")
+    }
+
+    /// Deep navy through blue and teal to pale cyan
+    pub fn ocean() -> Self {
+        Gradient::evenly_spaced(&[
+            Color::rgb(0, 5, 40),
+            Color::rgb(0, 40, 140),
+            Color::rgb(0, 130, 160),
+            Color::rgb(120, 230, 230),
+        ])
+        .expect("palette has stops")
+    }
+
+    /// Dark greens through leaf green to yellow green
+    pub fn forest() -> Self {
+        Gradient::evenly_spaced(&[
+            Color::rgb(0, 30, 5),
+            Color::rgb(20, 90, 20),
+            Color::rgb(60, 160, 30),
+            Color::rgb(170, 210, 60),
+        ])
+        .expect("palette has stops")
+    }
+
+    /// Purple through magenta and orange to yellow
+    pub fn sunset() -> Self {
+        Gradient::evenly_spaced(&[
+            Color::rgb(40, 0, 80),
+            Color::rgb(180, 20, 120),
+            Color::rgb(255, 100, 30),
+            Color::rgb(255, 210, 60),
+        ])
+        .expect("palette has stops")
+    }
+
+    /// White through pale blue to deep blue
+    pub fn ice() -> Self {
+        Gradient::evenly_spaced(&[
+            Color::rgb(255, 255, 255),
+            Color::rgb(150, 220, 255),
+            Color::rgb(30, 90, 255),
+        ])
+        .expect("palette has stops")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn new_gradient() {
+        assert!(Gradient::new(vec![]).is_err());
+        assert!(Gradient::new(vec![(f64::NAN, Color::rgb(0, 0, 0))]).is_err());
+
+        let gradient = Gradient::new(vec![
+            (1.0, Color::rgb(0, 0, 255)),
+            (0.0, Color::rgb(255, 0, 0)),
+        ])
+        .unwrap();
+        assert_eq!(gradient.stops()[0], (0.0, Color::rgb(255, 0, 0)));
+        assert_eq!(gradient.stops()[1], (1.0, Color::rgb(0, 0, 255)));
+    }
+
+    #[test]
+    fn sample() {
+        let gradient = Gradient::new(vec![
+            (0.0, Color::rgb(0, 0, 0)),
+            (0.5, Color::rgb(200, 0, 0)),
+            (1.0, Color::rgb(200, 100, 0)),
+        ])
+        .unwrap()
+        .with_interpolation(Interpolation::Srgb);
+
+        assert_eq!(gradient.sample(0.0), Color::rgb(0, 0, 0));
+        assert_eq!(gradient.sample(0.25), Color::rgb(100, 0, 0));
+        assert_eq!(gradient.sample(0.5), Color::rgb(200, 0, 0));
+        assert_eq!(gradient.sample(0.75), Color::rgb(200, 50, 0));
+        assert_eq!(gradient.sample(1.0), Color::rgb(200, 100, 0));
+
+        let single = Gradient::new(vec![(0.3, Color::rgb(1, 2, 3))]).unwrap();
+        assert_eq!(single.sample(-4.0), Color::rgb(1, 2, 3));
+        assert_eq!(single.sample(0.3), Color::rgb(1, 2, 3));
+        assert_eq!(single.sample(4.0), Color::rgb(1, 2, 3));
+    }
+
+    #[test]
+    fn modes() {
+        let gradient = Gradient::evenly_spaced(&[Color::rgb(0, 0, 0), Color::rgb(200, 0, 0)])
+            .unwrap()
+            .with_interpolation(Interpolation::Srgb);
+
+        assert_eq!(gradient.sample(-0.5), Color::rgb(0, 0, 0));
+        assert_eq!(gradient.sample(1.25), Color::rgb(200, 0, 0));
+
+        let gradient = gradient.with_mode(GradientMode::Wrap);
+        assert_eq!(gradient.sample(1.25), Color::rgb(50, 0, 0));
+        assert_eq!(gradient.sample(-0.25), Color::rgb(150, 0, 0));
+
+        let gradient = gradient.with_mode(GradientMode::Mirror);
+        assert_eq!(gradient.sample(1.25), Color::rgb(150, 0, 0));
+        assert_eq!(gradient.sample(-0.25), Color::rgb(50, 0, 0));
+        assert_eq!(gradient.sample(2.25), Color::rgb(50, 0, 0));
+    }
+
+    #[test]
+    fn sample_not_finite() {
+        let gradient = Gradient::evenly_spaced(&[
+            Color::rgb(0, 0, 0),
+            Color::rgb(100, 0, 0),
+            Color::rgb(200, 0, 0),
+        ])
+        .unwrap();
+        assert_eq!(gradient.sample(f64::NAN), Color::rgb(0, 0, 0));
+        assert_eq!(gradient.sample(f64::INFINITY), Color::rgb(200, 0, 0));
+        assert_eq!(gradient.sample(f64::NEG_INFINITY), Color::rgb(0, 0, 0));
+        for mode in [GradientMode::Wrap, GradientMode::Mirror] {
+            let gradient = gradient.clone().with_mode(mode);
+            assert_eq!(gradient.sample(f64::NAN), Color::rgb(0, 0, 0));
+            assert_eq!(gradient.sample(f64::INFINITY), Color::rgb(0, 0, 0));
+        }
+    }
+
+    #[test]
+    fn palettes() {
+        let rainbow = Gradient::rainbow().with_mode(GradientMode::Wrap);
+        assert_eq!(rainbow.sample(0.0), Color::rgb(255, 0, 0));
+        assert_eq!(rainbow.sample(1.0 / 3.0), Color::rgb(0, 255, 0));
+        assert_eq!(rainbow.sample(1.0 + 2.0 / 3.0), Color::rgb(0, 0, 255));
+        let (h, s, v) = rainbow.sample(0.25).to_hsv();
+        assert!((h - 90.0).abs() < 1.0 && s > 0.99 && v > 0.99);
+
+        assert_eq!(Gradient::heat().sample(0.0), Color::rgb(0, 0, 0));
+        assert_eq!(Gradient::heat().sample(1.0), Color::rgb(255, 255, 255));
+        for palette in [
+            Gradient::fire(),
+            Gradient::ocean(),
+            Gradient::forest(),
+            Gradient::sunset(),
+            Gradient::ice(),
+        ] {
+            assert!(palette.stops().len() >= 2);
+            assert_eq!(palette.sample(0.0), palette.stops()[0].1);
+        }
+    }
+}
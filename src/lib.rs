@@ -2,19 +2,38 @@
 extern crate kiss3d;
 
 pub mod arrangement;
+mod blend;
 mod color;
 mod error;
+mod falloff;
+mod gradient;
 mod light_strip;
 mod loc;
 mod math;
+mod metric;
 mod ntree;
+mod path;
+mod shape;
+mod transform;
 
 pub use arrangement::ArrangementConfig;
 pub use arrangement::LightArrangement;
-pub use color::Color;
+pub use arrangement::Topology;
+pub use arrangement::{Dither, FrameBufferConfig, ToneMap};
+pub use blend::BlendMode;
+pub use color::{Color, ColorA, ColorW, Interpolation, WhiteExtraction};
 pub use error::LightArrangementError;
+pub use falloff::Falloff;
+pub use gradient::{Gradient, GradientMode};
 pub use light_strip::{
-    ColorOrder, LightStrip, LightStripConfig, RealStrip, TestStrip, TestStripDisplayConfig,
-    Ws281xStrip,
+    ColorCorrection, ColorOrder, CorrectedStrip, LightStrip, LightStripConfig, RealStrip,
+    TestStrip, TestStripDisplayConfig, Ws281xStrip,
 };
 pub use loc::Loc;
+pub use metric::Metric;
+pub use path::Path;
+pub use shape::{
+    Capsule, Cone, ConvexHull, Cylinder, Difference, Ellipsoid, Intersection, OrientedBox, Plane,
+    Polygon, Shape, SmoothSubtraction, SmoothUnion, Sphere, Torus, Union,
+};
+pub use transform::Transform;
@@ -0,0 +1,446 @@
+use super::LightStrip;
+use crate::{
+    arrangement::ArrangementConfig,
+    color::{kelvin_to_rgb, unit_to_u8, Color, ColorW, WhiteExtraction},
+};
+
+/// Calibration applied to colors right before they are sent to a strip
+/// Effects are written in perceptual values, where 128 should look about half as bright as 255.
+/// LEDs are linear in the light they give off, so a gamma curve maps those values to duty cycles,
+/// then a color matrix, per channel scale and color temperature fix the white point of the strip.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorCorrection {
+    gamma: [f64; 3],
+    lut: [[f64; 256]; 3],
+    matrix: [[f64; 3]; 3],
+    scale: [f64; 3],
+    temperature: Option<f64>,
+}
+
+impl ColorCorrection {
+    /// Creates a correction that passes colors through unchanged
+    pub fn new() -> Self {
+        ColorCorrection {
+            gamma: [1.0; 3],
+            lut: [gamma_lut(1.0); 3],
+            matrix: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            scale: [1.0; 3],
+            temperature: None,
+        }
+    }
+
+    /// Uses `gamma` for the curve of every channel. WS2812 strips look even with around 2.2 to 2.8
+    pub fn with_gamma(self, gamma: f64) -> Self {
+        self.with_channel_gamma([gamma; 3])
+    }
+
+    /// Uses a separate gamma for the red, green and blue channels
+    pub fn with_channel_gamma(mut self, gamma: [f64; 3]) -> Self {
+        self.gamma = gamma;
+        self.lut = [
+            gamma_lut(gamma[0]),
+            gamma_lut(gamma[1]),
+            gamma_lut(gamma[2]),
+        ];
+        self
+    }
+
+    /// Multiplies the red, green and blue channels by `scale` after the gamma curve
+    /// Values are usually 0..1, turning down the channels that are too strong on the strip
+    pub fn with_channel_scale(mut self, scale: [f64; 3]) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Balances the channels of a strip whose full white looks like `white`, such as a white
+    /// measured with a reference meter or picked by eye against a reference strip. Strips from
+    /// different batches calibrated this way show the same whites, including `Color::from_kelvin`
+    /// ones. Replaces any scale set by `with_channel_scale`
+    pub fn with_white_point(self, white: &Color) -> Self {
+        let (r, g, b) = white.unit_components();
+        let weakest = r.min(g).min(b);
+        let scale = |c: f64| if c > 0.0 { weakest / c } else { 1.0 };
+        self.with_channel_scale([scale(r), scale(g), scale(b)])
+    }
+
+    /// Mixes the channels through `matrix` after the gamma curve, where each row gives the
+    /// weights of the input (r, g, b) for that output channel
+    pub fn with_matrix(mut self, matrix: [[f64; 3]; 3]) -> Self {
+        self.matrix = matrix;
+        self
+    }
+
+    /// Tints all output toward the color of a black body at `kelvin`, so full white on the strip
+    /// looks like a light of that color temperature. 6600K leaves colors nearly unchanged
+    pub fn with_temperature(mut self, kelvin: f64) -> Self {
+        self.temperature = Some(kelvin);
+        self
+    }
+
+    /// Removes the color temperature set by `with_temperature`
+    pub fn without_temperature(mut self) -> Self {
+        self.temperature = None;
+        self
+    }
+
+    pub fn gamma(&self) -> [f64; 3] {
+        self.gamma
+    }
+
+    pub fn temperature(&self) -> Option<f64> {
+        self.temperature
+    }
+
+    /// Returns the value that should be sent to the strip to display `color`
+    pub fn correct(&self, color: &Color) -> Color {
+        let input = [
+            self.lut[0][color.red as usize],
+            self.lut[1][color.green as usize],
+            self.lut[2][color.blue as usize],
+        ];
+
+        let tint = match self.temperature {
+            Some(kelvin) => {
+                let (r, g, b) = kelvin_to_rgb(kelvin);
+                [r, g, b]
+            }
+            None => [1.0; 3],
+        };
+
+        let mut output = [0.0; 3];
+        for (channel, out) in output.iter_mut().enumerate() {
+            let mixed: f64 = (0..3).map(|i| self.matrix[channel][i] * input[i]).sum();
+            *out = mixed * self.scale[channel] * tint[channel];
+        }
+
+        Color::rgb(
+            unit_to_u8(output[0]),
+            unit_to_u8(output[1]),
+            unit_to_u8(output[2]),
+        )
+    }
+}
+
+impl Default for ColorCorrection {
+    fn default() -> Self {
+        ColorCorrection::new()
+    }
+}
+
+/// Wraps a `LightStrip` and runs every color through a `ColorCorrection` on its way to the strip
+/// `get` returns the uncorrected colors that were set, so effects that read back and merge colors
+/// keep working in uncorrected values. Colors set with `set_rgbw` keep their white channel, and
+/// only their red, green and blue channels are corrected
+pub struct CorrectedStrip<T: LightStrip> {
+    strip: T,
+    correction: ColorCorrection,
+    colors: Vec<Color>,
+    /// White channel of lights set with `set_rgbw`, or `None` for lights set with `set`, where the
+    /// strip picks the white itself
+    whites: Vec<Option<u8>>,
+}
+
+impl<T: LightStrip> CorrectedStrip<T> {
+    /// Wraps `strip`, which holds the lights of `arrangement_config`, correcting all output with
+    /// `correction`
+    pub fn new<const N: usize>(
+        strip: T,
+        arrangement_config: &ArrangementConfig<N>,
+        correction: ColorCorrection,
+    ) -> Self {
+        let number_lights = arrangement_config.strip_length();
+        let mut corrected = CorrectedStrip {
+            colors: vec![Color::rgb(0, 0, 0); number_lights],
+            whites: vec![None; number_lights],
+            strip,
+            correction,
+        };
+        corrected.fill(&Color::rgb(0, 0, 0));
+        corrected
+    }
+
+    pub fn correction(&self) -> &ColorCorrection {
+        &self.correction
+    }
+
+    /// Replaces the correction and re-sends every light to the strip with it
+    pub fn set_correction(&mut self, correction: ColorCorrection) {
+        self.correction = correction;
+        for index in 0..self.colors.len() {
+            self.send(index);
+        }
+    }
+
+    /// The wrapped strip, holding corrected colors
+    pub fn strip(&self) -> &T {
+        &self.strip
+    }
+
+    pub fn into_inner(self) -> T {
+        self.strip
+    }
+
+    /// Sends the corrected color of the light at `index` to the strip
+    fn send(&mut self, index: usize) {
+        let corrected = self.correction.correct(&self.colors[index]);
+        match self.whites[index] {
+            Some(white) => {
+                let color = ColorW::rgbw(corrected.red, corrected.green, corrected.blue, white);
+                self.strip.set_rgbw(index, &color);
+            }
+            None => self.strip.set(index, &corrected),
+        }
+    }
+}
+
+impl<T: LightStrip> LightStrip for CorrectedStrip<T> {
+    #[inline]
+    fn get(&self, index: usize) -> Color {
+        match self.whites[index] {
+            Some(_) => self
+                .get_rgbw(index)
+                .to_color(WhiteExtraction::MinSubtraction),
+            None => self.colors[index],
+        }
+    }
+
+    #[inline]
+    fn set(&mut self, index: usize, color: &Color) {
+        self.colors[index] = *color;
+        self.whites[index] = None;
+        self.send(index);
+    }
+
+    fn show(&mut self) {
+        self.strip.show()
+    }
+
+    fn fill(&mut self, color: &Color) {
+        self.colors.iter_mut().for_each(|c| *c = *color);
+        self.whites.iter_mut().for_each(|w| *w = None);
+        self.strip.fill(&self.correction.correct(color));
+    }
+
+    #[inline]
+    fn get_rgbw(&self, index: usize) -> ColorW {
+        let color = self.colors[index];
+        let white = self.whites[index].unwrap_or(0);
+        ColorW::rgbw(color.red, color.green, color.blue, white)
+    }
+
+    #[inline]
+    fn set_rgbw(&mut self, index: usize, color: &ColorW) {
+        self.colors[index] = Color::rgb(color.red, color.green, color.blue);
+        self.whites[index] = Some(color.white);
+        self.send(index);
+    }
+}
+
+fn gamma_lut(gamma: f64) -> [f64; 256] {
+    let mut lut = [0.0; 256];
+    for (i, value) in lut.iter_mut().enumerate() {
+        *value = (i as f64 / 255.0).powf(gamma);
+    }
+    lut
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{arrangement::ArrangementConfig, TestStrip, TestStripDisplayConfig};
+
+    #[test]
+    fn identity() {
+        let correction = ColorCorrection::new();
+        for color in [
+            Color::rgb(0, 0, 0),
+            Color::rgb(255, 255, 255),
+            Color::rgb(12, 128, 201),
+        ] {
+            assert_eq!(correction.correct(&color), color);
+        }
+    }
+
+    #[test]
+    fn gamma() {
+        let correction = ColorCorrection::new().with_gamma(2.0);
+        assert_eq!(
+            correction.correct(&Color::rgb(0, 0, 0)),
+            Color::rgb(0, 0, 0)
+        );
+        assert_eq!(
+            correction.correct(&Color::rgb(255, 255, 255)),
+            Color::rgb(255, 255, 255)
+        );
+        assert_eq!(
+            correction.correct(&Color::rgb(128, 128, 128)),
+            Color::rgb(64, 64, 64)
+        );
+
+        let correction = ColorCorrection::new().with_channel_gamma([1.0, 2.0, 3.0]);
+        assert_eq!(
+            correction.correct(&Color::rgb(128, 128, 128)),
+            Color::rgb(128, 64, 32)
+        );
+    }
+
+    #[test]
+    fn scale_and_matrix() {
+        let correction = ColorCorrection::new().with_channel_scale([1.0, 0.5, 0.0]);
+        assert_eq!(
+            correction.correct(&Color::rgb(200, 200, 200)),
+            Color::rgb(200, 100, 0)
+        );
+
+        // swap red and blue
+        let correction =
+            ColorCorrection::new().with_matrix([[0.0, 0.0, 1.0], [0.0, 1.0, 0.0], [1.0, 0.0, 0.0]]);
+        assert_eq!(
+            correction.correct(&Color::rgb(10, 20, 30)),
+            Color::rgb(30, 20, 10)
+        );
+
+        // values clamp to the range of the strip
+        let correction = ColorCorrection::new().with_channel_scale([2.0, 2.0, 2.0]);
+        assert_eq!(
+            correction.correct(&Color::rgb(200, 100, 0)),
+            Color::rgb(255, 200, 0)
+        );
+    }
+
+    #[test]
+    fn white_point() {
+        // a bluish batch gets its blue turned down
+        let correction = ColorCorrection::new().with_white_point(&Color::rgb(200, 200, 250));
+        assert_eq!(
+            correction.correct(&Color::rgb(255, 255, 255)),
+            Color::rgb(255, 255, 204)
+        );
+
+        // the same temperature on two batches comes out matching what each batch needs
+        let warm = Color::from_kelvin(2700.0);
+        let batch_a = ColorCorrection::new().with_white_point(&Color::rgb(255, 255, 255));
+        let batch_b = ColorCorrection::new().with_white_point(&Color::rgb(255, 230, 255));
+        assert_eq!(batch_a.correct(&warm), warm);
+        let corrected = batch_b.correct(&warm);
+        assert_eq!(corrected.red, 230);
+        assert_eq!(corrected.green, warm.green);
+        assert_eq!(
+            corrected.blue,
+            (warm.blue as f64 * 230.0 / 255.0).round() as u8
+        );
+    }
+
+    #[test]
+    fn temperature() {
+        let white = Color::rgb(255, 255, 255);
+
+        let warm = ColorCorrection::new()
+            .with_temperature(2700.0)
+            .correct(&white);
+        assert_eq!(warm.red, 255);
+        assert!(warm.green < 200);
+        assert!(warm.blue < warm.green);
+
+        let neutral = ColorCorrection::new()
+            .with_temperature(6600.0)
+            .correct(&white);
+        assert!(neutral.red >= 250 && neutral.green >= 250 && neutral.blue >= 250);
+
+        let cool = ColorCorrection::new()
+            .with_temperature(10000.0)
+            .correct(&white);
+        assert_eq!(cool.blue, 255);
+        assert!(cool.red < 230);
+
+        let correction = ColorCorrection::new()
+            .with_temperature(2700.0)
+            .without_temperature();
+        assert_eq!(correction.correct(&white), white);
+    }
+
+    #[test]
+    fn corrected_strip() {
+        let arrangement_config = ArrangementConfig {
+            light_locations: vec![([0.0], 0), ([0.5], 1), ([1.0], 2)],
+            number_children_for_division: 1,
+        };
+        let test_strip = TestStrip::new(&arrangement_config, &TestStripDisplayConfig::default());
+        let correction = ColorCorrection::new().with_gamma(2.0);
+        let mut strip = CorrectedStrip::new(test_strip, &arrangement_config, correction);
+
+        strip.set(1, &Color::rgb(128, 0, 255));
+        assert_eq!(strip.get(1), Color::rgb(128, 0, 255));
+        assert_eq!(strip.strip().get(1), Color::rgb(64, 0, 255));
+        assert_eq!(strip.get(0), Color::rgb(0, 0, 0));
+
+        strip.fill(&Color::rgb(128, 128, 128));
+        assert_eq!(strip.get(2), Color::rgb(128, 128, 128));
+        assert_eq!(strip.strip().get(2), Color::rgb(64, 64, 64));
+
+        strip.set_correction(ColorCorrection::new());
+        assert_eq!(strip.get(2), Color::rgb(128, 128, 128));
+        assert_eq!(strip.strip().get(2), Color::rgb(128, 128, 128));
+    }
+
+    /// Strip with a white LED that keeps the white channel it is given
+    struct RgbwStrip {
+        lights: Vec<ColorW>,
+    }
+
+    impl LightStrip for RgbwStrip {
+        fn get(&self, index: usize) -> Color {
+            self.lights[index].to_color(WhiteExtraction::MinSubtraction)
+        }
+
+        fn set(&mut self, index: usize, color: &Color) {
+            self.lights[index] = ColorW::from_color(color, WhiteExtraction::MinSubtraction);
+        }
+
+        fn show(&mut self) {}
+
+        fn fill(&mut self, color: &Color) {
+            for index in 0..self.lights.len() {
+                self.set(index, color);
+            }
+        }
+
+        fn get_rgbw(&self, index: usize) -> ColorW {
+            self.lights[index]
+        }
+
+        fn set_rgbw(&mut self, index: usize, color: &ColorW) {
+            self.lights[index] = *color;
+        }
+    }
+
+    #[test]
+    fn corrected_rgbw_strip() {
+        let arrangement_config = ArrangementConfig {
+            light_locations: vec![([0.0], 0), ([1.0], 1)],
+            number_children_for_division: 1,
+        };
+        let rgbw_strip = RgbwStrip {
+            lights: vec![ColorW::rgbw(0, 0, 0, 0); 2],
+        };
+        let correction = ColorCorrection::new().with_gamma(2.0);
+        let mut strip = CorrectedStrip::new(rgbw_strip, &arrangement_config, correction);
+
+        // the white channel reaches the strip untouched while the rest is corrected
+        strip.set_rgbw(0, &ColorW::rgbw(128, 0, 0, 200));
+        assert_eq!(strip.get_rgbw(0), ColorW::rgbw(128, 0, 0, 200));
+        assert_eq!(strip.strip().get_rgbw(0), ColorW::rgbw(64, 0, 0, 200));
+        assert_eq!(strip.get(0), Color::rgb(255, 200, 200));
+
+        // plain colors still let the strip pick the white
+        strip.set(1, &Color::rgb(128, 128, 128));
+        assert_eq!(strip.get_rgbw(1), ColorW::rgbw(128, 128, 128, 0));
+        assert_eq!(strip.strip().get_rgbw(1), ColorW::rgbw(0, 0, 0, 64));
+
+        // and both are sent again when the correction changes
+        strip.set_correction(ColorCorrection::new());
+        assert_eq!(strip.strip().get_rgbw(0), ColorW::rgbw(128, 0, 0, 200));
+        assert_eq!(strip.strip().get_rgbw(1), ColorW::rgbw(0, 0, 0, 128));
+    }
+}
@@ -1,10 +1,15 @@
 /// Abstraction around the means to actually control the Light Strip. Establishes a common API across
 /// each strip type
+mod correction;
 mod test_strip;
 mod ws281x_strip;
 
-use crate::{color::Color, LightArrangementError};
+use crate::{
+    color::{Color, ColorW, WhiteExtraction},
+    LightArrangementError,
+};
 
+pub use correction::{ColorCorrection, CorrectedStrip};
 pub use test_strip::{TestStrip, TestStripDisplayConfig};
 pub use ws281x_strip::Ws281xStrip;
 
@@ -13,6 +18,18 @@ pub trait LightStrip {
     fn set(&mut self, index: usize, color: &Color);
     fn show(&mut self);
     fn fill(&mut self, color: &Color);
+
+    /// Gets the color of a light including its white channel. Strips without a white LED report
+    /// a white of 0
+    fn get_rgbw(&self, index: usize) -> ColorW {
+        ColorW::from_color(&self.get(index), WhiteExtraction::None)
+    }
+
+    /// Sets the color of a light including its white channel. Strips without a white LED mix the
+    /// white into the red, green and blue channels instead
+    fn set_rgbw(&mut self, index: usize, color: &ColorW) {
+        self.set(index, &color.to_color(WhiteExtraction::MinSubtraction));
+    }
 }
 
 /// implemented by Light Strips that are not simulations, such as Ws281x strips.
@@ -28,6 +45,7 @@ pub struct LightStripConfig {
     pub brightness: u8,
     pub order: ColorOrder,
     pub frequency: u32,
+    white_extraction: WhiteExtraction,
 }
 
 impl LightStripConfig {
@@ -37,6 +55,9 @@ impl LightStripConfig {
     /// `brightness`: 0..255 brightness value to set lights
     /// `order`: Color order of the strip
     /// `frequency`: Frequency of the signal to the LEDs, usually 800K
+    ///
+    /// RGBW strips move the gray part of each color to the white LED; use
+    /// `with_white_extraction` to change that
     pub fn new(
         number_lights: i32,
         io_pin: i32,
@@ -50,8 +71,15 @@ impl LightStripConfig {
             brightness,
             order,
             frequency,
+            white_extraction: WhiteExtraction::MinSubtraction,
         }
     }
+
+    /// Sets how RGBW strips derive the white channel from colors set on them
+    pub fn with_white_extraction(mut self, white_extraction: WhiteExtraction) -> Self {
+        self.white_extraction = white_extraction;
+        self
+    }
 }
 
 /// Color order of the light strip
@@ -62,4 +90,25 @@ pub enum ColorOrder {
     Gbr,
     Brg,
     Bgr,
+    Rgbw,
+    Rbgw,
+    Grbw,
+    Gbrw,
+    Brgw,
+    Bgrw,
+}
+
+impl ColorOrder {
+    /// true if strips with this order have a white LED
+    pub fn has_white(&self) -> bool {
+        matches!(
+            self,
+            ColorOrder::Rgbw
+                | ColorOrder::Rbgw
+                | ColorOrder::Grbw
+                | ColorOrder::Gbrw
+                | ColorOrder::Brgw
+                | ColorOrder::Bgrw
+        )
+    }
 }
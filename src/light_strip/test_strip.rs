@@ -25,7 +25,7 @@ impl TestStrip {
         arrangement_config: &ArrangementConfig<N>,
         display_config: &TestStripDisplayConfig,
     ) -> Self {
-        let lights = vec![(0, 0, 0); arrangement_config.light_locations.len()];
+        let lights = vec![(0, 0, 0); arrangement_config.strip_length()];
         let mut window = Window::new("Demo");
         let camera_start = &display_config.camera_start;
         let center_point = &display_config.center_point;
@@ -34,14 +34,17 @@ impl TestStrip {
             Point3::new(center_point.0, center_point.1, center_point.2),
         );
 
+        // indices without a light in the arrangement get a hidden sphere
         let mut objects: Vec<SceneNode> = vec![];
-        for i in 0..arrangement_config.light_locations.len() {
-            objects.push(window.add_sphere(display_config.sphere_size));
-            let pt = coord_to_3d_cord(
-                &arrangement_config.light_locations[i].0,
-                &display_config.dimension_mask,
-            );
-            objects[i].prepend_to_local_translation(&Translation3::new(pt[0], pt[1], pt[2]));
+        for _ in 0..lights.len() {
+            let mut object = window.add_sphere(display_config.sphere_size);
+            object.set_visible(false);
+            objects.push(object);
+        }
+        for (loc, index) in arrangement_config.light_locations.iter() {
+            let pt = coord_to_3d_cord(loc, &display_config.dimension_mask);
+            objects[*index].set_visible(true);
+            objects[*index].prepend_to_local_translation(&Translation3::new(pt[0], pt[1], pt[2]));
         }
 
         let mut strip = Self {
@@ -59,7 +62,7 @@ impl TestStrip {
         arrangement_config: &ArrangementConfig<N>,
         display_config: &TestStripDisplayConfig,
     ) -> Self {
-        let lights = vec![(0, 0, 0); arrangement_config.light_locations.len()];
+        let lights = vec![(0, 0, 0); arrangement_config.strip_length()];
         return Self { lights };
     }
 }
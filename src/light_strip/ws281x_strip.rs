@@ -4,6 +4,8 @@
 use rs_ws281x::{self, ChannelBuilder, Controller, ControllerBuilder};
 
 use super::{LightStrip, LightStripConfig, RealStrip};
+#[cfg(feature = "ws281x")]
+use crate::color::{ColorW, WhiteExtraction};
 use crate::{color::Color, LightArrangementError};
 
 const CHANNEL: usize = 0;
@@ -11,6 +13,9 @@ const CHANNEL: usize = 0;
 pub struct Ws281xStrip {
     #[cfg(feature = "ws281x")]
     controller: Controller,
+    /// How to split colors for the white LED, `None` if the strip has no white LED
+    #[cfg(feature = "ws281x")]
+    white_extraction: Option<WhiteExtraction>,
 }
 
 impl RealStrip for Ws281xStrip {
@@ -23,6 +28,17 @@ impl RealStrip for Ws281xStrip {
             super::ColorOrder::Gbr => rs_ws281x::StripType::Ws2811Gbr,
             super::ColorOrder::Brg => rs_ws281x::StripType::Ws2811Brg,
             super::ColorOrder::Bgr => rs_ws281x::StripType::Ws2811Bgr,
+            super::ColorOrder::Rgbw => rs_ws281x::StripType::Sk6812Rgbw,
+            super::ColorOrder::Rbgw => rs_ws281x::StripType::Sk6812Rbgw,
+            super::ColorOrder::Grbw => rs_ws281x::StripType::Sk6812Grbw,
+            super::ColorOrder::Gbrw => rs_ws281x::StripType::Sk6812Gbrw,
+            super::ColorOrder::Brgw => rs_ws281x::StripType::Sk6812Brgw,
+            super::ColorOrder::Bgrw => rs_ws281x::StripType::Sk6812Bgrw,
+        };
+        let white_extraction = if config.order.has_white() {
+            Some(config.white_extraction)
+        } else {
+            None
         };
 
         let controller = ControllerBuilder::new()
@@ -39,7 +55,10 @@ impl RealStrip for Ws281xStrip {
             )
             .build();
         return match controller {
-            Ok(controller) => Ok(Ws281xStrip { controller }),
+            Ok(controller) => Ok(Ws281xStrip {
+                controller,
+                white_extraction,
+            }),
             Err(error) => Err(LightArrangementError::from_error(error)),
         };
     }
@@ -50,21 +69,47 @@ impl RealStrip for Ws281xStrip {
     }
 }
 
+#[cfg(feature = "ws281x")]
+impl Ws281xStrip {
+    /// Splits `color` into the raw value for the strip, moving part of it to the white LED if
+    /// the strip has one
+    #[inline]
+    fn to_raw(&self, color: &Color) -> [u8; 4] {
+        let color_w = ColorW::from_color(
+            color,
+            self.white_extraction.unwrap_or(WhiteExtraction::None),
+        );
+        [color_w.red, color_w.green, color_w.blue, color_w.white]
+    }
+}
+
 #[cfg(feature = "ws281x")]
 impl LightStrip for Ws281xStrip {
     #[inline]
     fn get(&self, index: usize) -> Color {
-        let raw = self.controller.leds(CHANNEL)[index];
-        return Color {
-            red: raw[0],
-            green: raw[1],
-            blue: raw[2],
-        };
+        self.get_rgbw(index)
+            .to_color(self.white_extraction.unwrap_or(WhiteExtraction::None))
     }
 
     #[inline]
     fn set(&mut self, index: usize, color: &Color) {
-        self.controller.leds_mut(CHANNEL)[index] = [color.red, color.green, color.blue, 0];
+        self.controller.leds_mut(CHANNEL)[index] = self.to_raw(color);
+    }
+
+    #[inline]
+    fn get_rgbw(&self, index: usize) -> ColorW {
+        let raw = self.controller.leds(CHANNEL)[index];
+        ColorW::rgbw(raw[0], raw[1], raw[2], raw[3])
+    }
+
+    #[inline]
+    fn set_rgbw(&mut self, index: usize, color: &ColorW) {
+        if self.white_extraction.is_some() {
+            self.controller.leds_mut(CHANNEL)[index] =
+                [color.red, color.green, color.blue, color.white];
+        } else {
+            self.set(index, &color.to_color(WhiteExtraction::MinSubtraction));
+        }
     }
 
     #[inline]
@@ -74,15 +119,11 @@ impl LightStrip for Ws281xStrip {
 
     #[inline]
     fn fill(&mut self, color: &Color) {
+        let value = self.to_raw(color);
         self.controller
             .leds_mut(CHANNEL)
             .iter_mut()
-            .for_each(|raw| {
-                raw[0] = color.red;
-                raw[1] = color.green;
-                raw[2] = color.blue;
-                raw[3] = 0;
-            });
+            .for_each(|raw| *raw = value);
     }
 }
 
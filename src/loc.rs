@@ -1,7 +1,12 @@
+use std::f64::consts::PI;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use crate::math::{array_map, array_zip, distance};
+
 /// Interface for talking about locations in coordinate space
 /// Converts cartesian, polar, and cylindrical into float arrays
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Loc<const N: usize> {
     pub coords: [f64; N],
 }
@@ -55,12 +60,167 @@ impl<const N: usize> Loc<N> {
         };
         return Loc { coords: loc_coords };
     }
+
+    /// Returns the polar coordinates of this point centered on `center`, as
+    /// (rho, angular_coords), following the same conventions as `Loc::polar`
+    ///
+    /// There are N - 1 angular coordinates. Coordinates 0..(N - 2) are in 0..=pi and the final
+    /// one is in 0..(2 * pi). Where an angle is not defined, such as at the center, it is 0.
+    /// For 1 dimension there are no angles, and rho is the signed offset from the center
+    pub fn to_polar(&self, center: &[f64; N]) -> (f64, Vec<f64>) {
+        let mut offset = [0.0; N];
+        for (i, o) in offset.iter_mut().enumerate() {
+            *o = self.coords[i] - center[i];
+        }
+        if N == 1 {
+            return (offset[0], vec![]);
+        }
+
+        // tails[i] is the length of offset[i..]
+        let mut tails = vec![0.0_f64; N + 1];
+        for i in (0..N).rev() {
+            tails[i] = tails[i + 1].hypot(offset[i]);
+        }
+
+        let mut angular_coords = Vec::with_capacity(N - 1);
+        for i in 0..(N - 2) {
+            angular_coords.push(tails[i + 1].atan2(offset[i]));
+        }
+        let last = offset[N - 1].atan2(offset[N - 2]);
+        angular_coords.push(last.rem_euclid(2.0 * PI));
+
+        (tails[0], angular_coords)
+    }
+
+    /// Returns the cylindrical coordinates of this point based at `origin`, as
+    /// (radius, theta, coords), following the same conventions as `Loc::cylindrical`
+    ///
+    /// `theta` is in 0..(2 * pi), measured from the first axis. `coords` are the offsets
+    /// from `origin` in the dimensions after the first 2
+    pub fn to_cylindrical(&self, origin: &[f64; N]) -> (f64, f64, Vec<f64>) {
+        match N {
+            n if n >= 2 => {
+                let x = self.coords[0] - origin[0];
+                let y = self.coords[1] - origin[1];
+                let coords = (2..N).map(|i| self.coords[i] - origin[i]).collect();
+                (x.hypot(y), y.atan2(x).rem_euclid(2.0 * PI), coords)
+            }
+            1 => {
+                let x = self.coords[0] - origin[0];
+                let theta = if x < 0.0 { PI } else { 0.0 };
+                (x.abs(), theta, vec![])
+            }
+            _ => (0.0, 0.0, vec![]),
+        }
+    }
+
+    /// Dot product of this and `other` as vectors
+    pub fn dot(&self, other: &Loc<N>) -> f64 {
+        (0..N).map(|i| self.coords[i] * other.coords[i]).sum()
+    }
+
+    /// Length of this as a vector, its distance from the origin
+    pub fn norm(&self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    /// Returns this scaled to length 1, or unchanged if it has length 0
+    pub fn normalize(&self) -> Loc<N> {
+        let norm = self.norm();
+        if norm == 0.0 {
+            *self
+        } else {
+            *self * (1.0 / norm)
+        }
+    }
+
+    /// Returns the point `t` of the way from this to `other`, where 0 is this and 1 is `other`
+    pub fn lerp(&self, other: &Loc<N>, t: f64) -> Loc<N> {
+        Loc {
+            coords: array_zip(&self.coords, &other.coords, &|(a, b)| a + (b - a) * t),
+        }
+    }
+
+    pub fn distance_to(&self, other: &Loc<N>) -> f64 {
+        distance(&self.coords, &other.coords)
+    }
+
+    /// Returns this with every coordinate clamped to 0..1, the space lights are arranged in
+    pub fn clamp_to_unit(&self) -> Loc<N> {
+        Loc {
+            coords: array_map(&self.coords, &|c| c.clamp(0.0, 1.0)),
+        }
+    }
+
+    /// Returns the smallest of this and `other` in each coordinate
+    pub fn min(&self, other: &Loc<N>) -> Loc<N> {
+        Loc {
+            coords: array_zip(&self.coords, &other.coords, &|(a, b)| a.min(*b)),
+        }
+    }
+
+    /// Returns the largest of this and `other` in each coordinate
+    pub fn max(&self, other: &Loc<N>) -> Loc<N> {
+        Loc {
+            coords: array_zip(&self.coords, &other.coords, &|(a, b)| a.max(*b)),
+        }
+    }
+}
+
+impl<const N: usize> Add for Loc<N> {
+    type Output = Loc<N>;
+
+    fn add(self, other: Loc<N>) -> Loc<N> {
+        Loc {
+            coords: array_zip(&self.coords, &other.coords, &|(a, b)| a + b),
+        }
+    }
+}
+
+impl<const N: usize> AddAssign for Loc<N> {
+    fn add_assign(&mut self, other: Loc<N>) {
+        *self = *self + other;
+    }
+}
+
+impl<const N: usize> Sub for Loc<N> {
+    type Output = Loc<N>;
+
+    fn sub(self, other: Loc<N>) -> Loc<N> {
+        Loc {
+            coords: array_zip(&self.coords, &other.coords, &|(a, b)| a - b),
+        }
+    }
+}
+
+impl<const N: usize> SubAssign for Loc<N> {
+    fn sub_assign(&mut self, other: Loc<N>) {
+        *self = *self - other;
+    }
+}
+
+impl<const N: usize> Mul<f64> for Loc<N> {
+    type Output = Loc<N>;
+
+    fn mul(self, scale: f64) -> Loc<N> {
+        Loc {
+            coords: array_map(&self.coords, &|c| c * scale),
+        }
+    }
+}
+
+impl<const N: usize> Neg for Loc<N> {
+    type Output = Loc<N>;
+
+    fn neg(self) -> Loc<N> {
+        Loc {
+            coords: array_map(&self.coords, &|c| -c),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use std::f64::consts::PI;
-
     use super::*;
 
     fn sqrt_2_over_2() -> f64 {
@@ -205,4 +365,163 @@ mod test {
         let top_right = Loc::cylindrical(0.5, PI, vec![0.0], &[0.5, 0.5, 1.0]);
         assert!(approx(&top_right.coords, &[0.0, 0.5, 1.0]));
     }
+
+    fn approx_vec(v1: &[f64], v2: &[f64]) -> bool {
+        v1.len() == v2.len() && v1.iter().zip(v2).all(|(a, b)| (a - b).abs() < 0.001)
+    }
+
+    #[test]
+    fn to_polar() {
+        // 2D
+        let (rho, angles) = Loc::cartesian([0.5, 1.0]).to_polar(&[0.5, 0.5]);
+        assert!((rho - 0.5).abs() < 0.001);
+        assert!(approx_vec(&angles, &[PI / 2.0]));
+        let (rho, angles) = Loc::cartesian([0.5, 0.0]).to_polar(&[0.5, 0.5]);
+        assert!((rho - 0.5).abs() < 0.001);
+        assert!(approx_vec(&angles, &[(3.0 * PI) / 2.0]));
+        let (rho, angles) = Loc::cartesian([0.5, 0.5]).to_polar(&[0.5, 0.5]);
+        assert_eq!(rho, 0.0);
+        assert!(approx_vec(&angles, &[0.0]));
+
+        // 3D
+        let (rho, angles) = Loc::cartesian([0.5, 0.5, 1.0]).to_polar(&[0.5, 0.5, 0.5]);
+        assert!((rho - 0.5).abs() < 0.001);
+        assert!(approx_vec(&angles, &[PI / 2.0, PI / 2.0]));
+        let (rho, angles) = Loc::cartesian([0.0, 0.5, 0.5]).to_polar(&[0.5, 0.5, 0.5]);
+        assert!((rho - 0.5).abs() < 0.001);
+        assert!(approx_vec(&angles, &[PI, 0.0]));
+
+        // 1D
+        let (rho, angles) = Loc::cartesian([0.2]).to_polar(&[0.5]);
+        assert!((rho + 0.3).abs() < 0.001);
+        assert!(angles.is_empty());
+    }
+
+    #[test]
+    fn polar_round_trips() {
+        let values = [0.0, 0.3, 0.5, 0.9, 1.0];
+        for &x in values.iter() {
+            for &y in values.iter() {
+                let center = [0.4, 0.6];
+                let loc = Loc::cartesian([x, y]);
+                let (rho, angles) = loc.to_polar(&center);
+                assert!(angles[0] >= 0.0 && angles[0] < 2.0 * PI);
+                assert!(approx(
+                    &Loc::polar(rho, &angles, &center).coords,
+                    &loc.coords
+                ));
+
+                for &z in values.iter() {
+                    let center = [0.5, 0.5, 0.5];
+                    let loc = Loc::cartesian([x, y, z]);
+                    let (rho, angles) = loc.to_polar(&center);
+                    assert!(angles[0] >= 0.0 && angles[0] <= PI);
+                    assert!(approx(
+                        &Loc::polar(rho, &angles, &center).coords,
+                        &loc.coords
+                    ));
+
+                    for &w in values.iter() {
+                        let center = [0.1, 0.5, 0.7, 0.3];
+                        let loc = Loc::cartesian([x, y, z, w]);
+                        let (rho, angles) = loc.to_polar(&center);
+                        assert_eq!(angles.len(), 3);
+                        assert!(angles[0] >= 0.0 && angles[0] <= PI);
+                        assert!(angles[1] >= 0.0 && angles[1] <= PI);
+                        assert!(angles[2] >= 0.0 && angles[2] < 2.0 * PI);
+                        assert!(approx(
+                            &Loc::polar(rho, &angles, &center).coords,
+                            &loc.coords
+                        ));
+                    }
+                }
+            }
+        }
+
+        // and the other way around, away from the poles where angles are not unique
+        let (rho, angles) = Loc::polar(0.3, &vec![1.0, 2.0, 5.0], &[0.5; 4]).to_polar(&[0.5; 4]);
+        assert!((rho - 0.3).abs() < 0.001);
+        assert!(approx_vec(&angles, &[1.0, 2.0, 5.0]));
+    }
+
+    #[test]
+    fn to_cylindrical() {
+        let (radius, theta, coords) =
+            Loc::cartesian([0.5, 1.0, 0.2]).to_cylindrical(&[0.5, 0.5, 0.0]);
+        assert!((radius - 0.5).abs() < 0.001);
+        assert!((theta - PI / 2.0).abs() < 0.001);
+        assert!(approx_vec(&coords, &[0.2]));
+
+        let (radius, theta, coords) = Loc::cartesian([0.5, 0.0]).to_cylindrical(&[0.5, 0.5]);
+        assert!((radius - 0.5).abs() < 0.001);
+        assert!((theta - (3.0 * PI) / 2.0).abs() < 0.001);
+        assert!(coords.is_empty());
+
+        let (radius, theta, _) = Loc::cartesian([0.2]).to_cylindrical(&[0.5]);
+        assert!((radius - 0.3).abs() < 0.001);
+        assert!((theta - PI).abs() < 0.001);
+    }
+
+    #[test]
+    fn cylindrical_round_trips() {
+        let values = [0.0, 0.3, 0.5, 0.9, 1.0];
+        for &x in values.iter() {
+            for &y in values.iter() {
+                let origin = [0.4, 0.6];
+                let loc = Loc::cartesian([x, y]);
+                let (radius, theta, coords) = loc.to_cylindrical(&origin);
+                let back = Loc::cylindrical(radius, theta, coords, &origin);
+                assert!(approx(&back.coords, &loc.coords));
+
+                for &z in values.iter() {
+                    let origin = [0.5, 0.5, 0.0];
+                    let loc = Loc::cartesian([x, y, z]);
+                    let (radius, theta, coords) = loc.to_cylindrical(&origin);
+                    let back = Loc::cylindrical(radius, theta, coords, &origin);
+                    assert!(approx(&back.coords, &loc.coords));
+
+                    for &w in values.iter() {
+                        let origin = [0.5, 0.5, 0.2, 0.1];
+                        let loc = Loc::cartesian([x, y, z, w]);
+                        let (radius, theta, coords) = loc.to_cylindrical(&origin);
+                        assert_eq!(coords.len(), 2);
+                        let back = Loc::cylindrical(radius, theta, coords, &origin);
+                        assert!(approx(&back.coords, &loc.coords));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = Loc::cartesian([0.1, 0.2, 0.3]);
+        let b = Loc::cartesian([0.4, 0.4, 0.0]);
+        assert!(approx(&(a + b).coords, &[0.5, 0.6, 0.3]));
+        assert!(approx(&(b - a).coords, &[0.3, 0.2, -0.3]));
+        assert!(approx(&(a * 2.0).coords, &[0.2, 0.4, 0.6]));
+        assert!(approx(&(-a).coords, &[-0.1, -0.2, -0.3]));
+
+        let mut c = a;
+        c += b;
+        c -= a;
+        assert!(approx(&c.coords, &b.coords));
+    }
+
+    #[test]
+    fn vector_methods() {
+        let a = Loc::cartesian([0.3, 0.4]);
+        let b = Loc::cartesian([1.0, -2.0]);
+        assert!((a.dot(&b) + 0.5).abs() < 0.001);
+        assert!((a.norm() - 0.5).abs() < 0.001);
+        assert!(approx(&a.normalize().coords, &[0.6, 0.8]));
+        assert_eq!(Loc::cartesian([0.0, 0.0]).normalize().coords, [0.0, 0.0]);
+        assert!(approx(&a.lerp(&b, 0.0).coords, &a.coords));
+        assert!(approx(&a.lerp(&b, 1.0).coords, &b.coords));
+        assert!(approx(&a.lerp(&b, 0.5).coords, &[0.65, -0.8]));
+        assert!((a.distance_to(&Loc::cartesian([0.0, 0.0])) - 0.5).abs() < 0.001);
+        assert_eq!(b.clamp_to_unit().coords, [1.0, 0.0]);
+        assert_eq!(a.min(&b).coords, [0.3, -2.0]);
+        assert_eq!(a.max(&b).coords, [1.0, 0.4]);
+    }
 }
@@ -36,6 +36,40 @@ pub fn array_zip<const N: usize>(
         )
 }
 
+/// Returns the corners of the smallest box holding every point `p` in the unit box with
+/// `low <= normal · p <= high`, or `None` if there are no such points
+pub fn slab_in_unit_box<const N: usize>(
+    normal: &[f64; N],
+    low: f64,
+    high: f64,
+) -> Option<([f64; N], [f64; N])> {
+    let mut lower = [0.0; N];
+    let mut upper = [1.0; N];
+    for i in 0..N {
+        // range of `normal · p` from every axis but `i` over the unit box
+        let rest = (0..N).filter(|j| *j != i).map(|j| normal[j]);
+        let rest_min: f64 = rest.clone().map(|n| n.min(0.0)).sum();
+        let rest_max: f64 = rest.map(|n| n.max(0.0)).sum();
+        let (a, b) = (low - rest_max, high - rest_min);
+        let n = normal[i];
+        let (from, to) = if n > 0.0 {
+            (a / n, b / n)
+        } else if n < 0.0 {
+            (b / n, a / n)
+        } else if a <= 0.0 && 0.0 <= b {
+            (0.0, 1.0)
+        } else {
+            return None;
+        };
+        lower[i] = from.max(0.0);
+        upper[i] = to.min(1.0);
+        if lower[i] > upper[i] {
+            return None;
+        }
+    }
+    Some((lower, upper))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -85,6 +119,33 @@ mod test {
         assert_eq!(res, [0.0, 2.0, 2.0, 0.0]);
     }
 
+    #[test]
+    fn test_slab_in_unit_box() {
+        let approx = |a: [f64; 2], b: [f64; 2]| (0..2).all(|i| (a[i] - b[i]).abs() < 0.0001);
+
+        let (lower, upper) = slab_in_unit_box(&[1.0, 0.0], 0.2, 0.4).unwrap();
+        assert!(approx(lower, [0.2, 0.0]) && approx(upper, [0.4, 1.0]));
+
+        // the corner of the box below the diagonal x + y = 0.5
+        let (lower, upper) = slab_in_unit_box(&[1.0, 1.0], f64::NEG_INFINITY, 0.5).unwrap();
+        assert!(approx(lower, [0.0, 0.0]) && approx(upper, [0.5, 0.5]));
+
+        // the same corner facing the other way
+        let (lower, upper) = slab_in_unit_box(&[-1.0, -1.0], -0.5, f64::INFINITY).unwrap();
+        assert!(approx(lower, [0.0, 0.0]) && approx(upper, [0.5, 0.5]));
+
+        // a thin band along the other diagonal only cuts off its corners
+        let (lower, upper) = slab_in_unit_box(&[1.0, -1.0], -0.1, 0.1).unwrap();
+        assert!(approx(lower, [0.0, 0.0]) && approx(upper, [1.0, 1.0]));
+
+        assert_eq!(slab_in_unit_box(&[1.0, 1.0], 2.5, 3.0), None);
+        assert_eq!(slab_in_unit_box(&[0.0, 1.0], -1.0, -0.5), None);
+        assert_eq!(
+            slab_in_unit_box(&[0.0, 0.0], -1.0, 1.0),
+            Some(([0.0, 0.0], [1.0, 1.0]))
+        );
+    }
+
     #[test]
     fn test_clamp() {
         assert_eq!(clamp(1.0, 0.0, 2.0), 1.0);
@@ -0,0 +1,94 @@
+/// Ways of measuring the distance between two points, which decide the shape of every "radius"
+/// in queries and effects: a circle for `Euclidean`, a diamond for `Manhattan` and a square for
+/// `Chebyshev`
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum Metric<const N: usize> {
+    /// Straight line distance
+    #[default]
+    Euclidean,
+    /// Sum of the distance along each axis
+    Manhattan,
+    /// Largest distance along any one axis
+    Chebyshev,
+    /// Straight line distance with the distance along each axis multiplied by a weight first
+    /// A weight of 2 on an axis squashes circles to half as tall along it, and a weight of 0
+    /// ignores it
+    WeightedEuclidean([f64; N]),
+    /// `(sum of |distance along each axis| ^ p) ^ (1 / p)`. A `p` of 1 is `Manhattan`, 2 is
+    /// `Euclidean` and infinity is `Chebyshev`. `p` should be above 0
+    Minkowski(f64),
+}
+
+impl<const N: usize> Metric<N> {
+    /// Returns the distance between `p1` and `p2`
+    #[inline]
+    pub fn distance(&self, p1: &[f64; N], p2: &[f64; N]) -> f64 {
+        let offsets = (0..N).map(|i| (p2[i] - p1[i]).abs());
+        match self {
+            Metric::Euclidean => offsets.map(|d| d * d).sum::<f64>().sqrt(),
+            Metric::Manhattan => offsets.sum(),
+            Metric::Chebyshev => offsets.fold(0.0, f64::max),
+            Metric::WeightedEuclidean(weights) => offsets
+                .zip(weights.iter())
+                .map(|(d, w)| (d * w).powi(2))
+                .sum::<f64>()
+                .sqrt(),
+            Metric::Minkowski(p) if p.is_infinite() => offsets.fold(0.0, f64::max),
+            Metric::Minkowski(p) => offsets.map(|d| d.powf(*p)).sum::<f64>().powf(1.0 / p),
+        }
+    }
+
+    /// Returns how far along each axis a point `radius` away can be, which is half the size of
+    /// the axis aligned box holding everything within `radius`
+    /// Axes that a `WeightedEuclidean` metric ignores have an infinite extent
+    pub fn extents(&self, radius: f64) -> [f64; N] {
+        match self {
+            Metric::WeightedEuclidean(weights) => {
+                let mut extents = [0.0; N];
+                for (extent, weight) in extents.iter_mut().zip(weights.iter()) {
+                    *extent = if *weight == 0.0 {
+                        f64::INFINITY
+                    } else {
+                        radius / weight.abs()
+                    };
+                }
+                extents
+            }
+            // no single coordinate can be further than the whole distance
+            _ => [radius; N],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn distances() {
+        let (p1, p2) = ([0.1, 0.2], [0.4, 0.6]);
+        assert!((Metric::Euclidean.distance(&p1, &p2) - 0.5).abs() < 0.0001);
+        assert!((Metric::Manhattan.distance(&p1, &p2) - 0.7).abs() < 0.0001);
+        assert!((Metric::Chebyshev.distance(&p1, &p2) - 0.4).abs() < 0.0001);
+        assert!((Metric::WeightedEuclidean([1.0, 0.0]).distance(&p1, &p2) - 0.3).abs() < 0.0001);
+        assert!(
+            (Metric::WeightedEuclidean([2.0, 1.0]).distance(&p1, &p2) - 0.52_f64.sqrt()).abs()
+                < 0.0001
+        );
+        assert!((Metric::Minkowski(1.0).distance(&p1, &p2) - 0.7).abs() < 0.0001);
+        assert!((Metric::Minkowski(2.0).distance(&p1, &p2) - 0.5).abs() < 0.0001);
+        assert!((Metric::Minkowski(f64::INFINITY).distance(&p1, &p2) - 0.4).abs() < 0.0001);
+        let cubic = (0.3_f64.powi(3) + 0.4_f64.powi(3)).cbrt();
+        assert!((Metric::Minkowski(3.0).distance(&p1, &p2) - cubic).abs() < 0.0001);
+    }
+
+    #[test]
+    fn extents() {
+        assert_eq!(Metric::<2>::Euclidean.extents(0.5), [0.5, 0.5]);
+        assert_eq!(Metric::<2>::Chebyshev.extents(0.5), [0.5, 0.5]);
+        assert_eq!(
+            Metric::WeightedEuclidean([2.0, 0.0, 0.5]).extents(0.5),
+            [0.25, f64::INFINITY, 1.0]
+        );
+    }
+}
@@ -7,7 +7,10 @@ use intersection::box_intersection;
 
 use tpntree::{tpntree::SpatialTree, TpnTreeError};
 
-use crate::math::{array_map, array_zip, distance};
+use crate::loc::Loc;
+use crate::math::array_zip;
+use crate::metric::Metric;
+use crate::shape::{Ellipsoid, Shape};
 
 use self::{
     intersection::point_intersection,
@@ -38,25 +41,28 @@ impl<T, const N: usize> NTree<T, N> {
         })
     }
 
-    /// Returns the datapoint closest to `point` that is <= `max_distance` away from `point`
+    /// Returns the datapoint closest to `point` that is <= `max_distance` away from `point`, with
+    /// distances measured by `metric`
     pub fn find_closest(
         &self,
         point: &[f64; N],
         max_distance: f64,
+        metric: &Metric<N>,
     ) -> Result<Option<&DataPoint<T, N>>, TpnTreeError> {
         if !spans(&self.root, point) {
             return Err(TpnTreeError::DoesNotSpan);
         }
 
-        let corner1 = array_map(point, &|x| x - max_distance);
-        let corner2 = array_map(point, &|x| x + max_distance);
+        let extents = metric.extents(max_distance);
+        let corner1 = array_zip(point, &extents, &|(x, e)| x - e);
+        let corner2 = array_zip(point, &extents, &|(x, e)| x + e);
 
         let points = self.find_in_box(&corner1, &corner2);
 
         let mut closest = None;
         let mut closest_distance = f64::MAX;
         for p in points {
-            let d = distance(&p.point, point);
+            let d = metric.distance(&p.point, point);
             if d < closest_distance && d < max_distance {
                 closest = Some(p);
                 closest_distance = d;
@@ -80,20 +86,31 @@ impl<T, const N: usize> NTree<T, N> {
         return datapoints;
     }
 
-    pub fn find_in_radius(&self, point: &[f64; N], radius: f64) -> Vec<&DataPoint<T, N>> {
-        let corner1 = array_map(&point, &|x| x - radius);
-        let corner2 = array_map(&point, &|x| x + radius);
-        let points = self.find_in_box(&corner1, &corner2);
-
-        if points.len() >= 1 {
-            let p = points[0];
-            assert!(p.point[0] > -999.0);
-        }
+    /// Returns all datapoints less than `radius` away from `point`, with distances measured by
+    /// `metric`
+    pub fn find_in_radius(
+        &self,
+        point: &[f64; N],
+        radius: f64,
+        metric: &Metric<N>,
+    ) -> Vec<&DataPoint<T, N>> {
+        let extents = metric.extents(radius);
+        let corner1 = array_zip(point, &extents, &|(x, e)| x - e);
+        let corner2 = array_zip(point, &extents, &|(x, e)| x + e);
+
+        self.find_in_box(&corner1, &corner2)
+            .into_iter()
+            .filter(|x| metric.distance(point, &x.point) < radius)
+            .collect()
+    }
 
-        return points
+    /// Returns every point inside `ellipsoid`, checking only those in its bounding box
+    pub fn find_in_ellipsoid(&self, ellipsoid: &Ellipsoid<N>) -> Vec<&DataPoint<T, N>> {
+        let (corner1, corner2) = ellipsoid.bounding_box();
+        self.find_in_box(&corner1.coords, &corner2.coords)
             .into_iter()
-            .filter(|x| -> bool { distance(point, &x.point) < radius })
-            .collect();
+            .filter(|x| ellipsoid.normalized_distance(&Loc::cartesian(x.point)) < 1.0)
+            .collect()
     }
 }
 
@@ -128,7 +145,10 @@ fn trees_in_box<'a, T, const N: usize>(
 
 #[cfg(test)]
 mod test {
+    use std::f64::consts::PI;
+
     use super::*;
+    use crate::{loc::Loc, transform::Transform};
 
     #[test]
     fn create_tree() {
@@ -188,17 +208,17 @@ mod test {
         assert!(root.insert(4, [0.8]).is_ok());
 
         // query points
-        let q1 = root.find_closest(&[0.1], 1.0)?;
+        let q1 = root.find_closest(&[0.1], 1.0, &Metric::Euclidean)?;
         assert_eq!(q1.unwrap().data, 1);
-        let q2 = root.find_closest(&[0.2999], 1.0)?;
+        let q2 = root.find_closest(&[0.2999], 1.0, &Metric::Euclidean)?;
         assert_eq!(q2.unwrap().data, 1);
-        let q3 = root.find_closest(&[0.35], 1.0)?;
+        let q3 = root.find_closest(&[0.35], 1.0, &Metric::Euclidean)?;
         assert_eq!(q3.unwrap().data, 2);
-        let q4 = root.find_closest(&[0.3999], 1.0)?;
+        let q4 = root.find_closest(&[0.3999], 1.0, &Metric::Euclidean)?;
         assert_eq!(q4.unwrap().data, 2);
-        let q5 = root.find_closest(&[0.59], 1.0)?;
+        let q5 = root.find_closest(&[0.59], 1.0, &Metric::Euclidean)?;
         assert_eq!(q5.unwrap().data, 3);
-        let q6 = root.find_closest(&[1.0], 1.0)?;
+        let q6 = root.find_closest(&[1.0], 1.0, &Metric::Euclidean)?;
         assert_eq!(q6.unwrap().data, 4);
 
         Ok(())
@@ -214,13 +234,13 @@ mod test {
         assert!(root.insert(4, [0.0, 1.0, 0.0]).is_ok());
 
         // query points
-        let q1 = root.find_closest(&[0.5, 0.5, 0.5], 1.0)?;
+        let q1 = root.find_closest(&[0.5, 0.5, 0.5], 1.0, &Metric::Euclidean)?;
         assert_eq!(q1.unwrap().data, 3);
-        let q2 = root.find_closest(&[0.1, 0.1, 0.1], 1.0)?;
+        let q2 = root.find_closest(&[0.1, 0.1, 0.1], 1.0, &Metric::Euclidean)?;
         assert_eq!(q2.unwrap().data, 1);
-        let q3 = root.find_closest(&[0.9, 0.9, 0.9], 1.0)?;
+        let q3 = root.find_closest(&[0.9, 0.9, 0.9], 1.0, &Metric::Euclidean)?;
         assert_eq!(q3.unwrap().data, 2);
-        let q4 = root.find_closest(&[0.2, 0.9, 0.1], 1.0)?;
+        let q4 = root.find_closest(&[0.2, 0.9, 0.1], 1.0, &Metric::Euclidean)?;
         assert_eq!(q4.unwrap().data, 4);
 
         Ok(())
@@ -314,7 +334,7 @@ mod test {
         assert!(root.insert(4, [0.0, 1.0, 0.0]).is_ok());
 
         let mut res: Vec<i32> = root
-            .find_in_radius(&[0.0, 0.0, 0.0], 0.25)
+            .find_in_radius(&[0.0, 0.0, 0.0], 0.25, &Metric::Euclidean)
             .iter()
             .map(|p| p.data)
             .collect();
@@ -322,7 +342,7 @@ mod test {
         assert_eq!(res, vec![1]);
 
         let mut res: Vec<i32> = root
-            .find_in_radius(&[0.0, 0.0, 0.0], 0.89)
+            .find_in_radius(&[0.0, 0.0, 0.0], 0.89, &Metric::Euclidean)
             .iter()
             .map(|p| p.data)
             .collect();
@@ -330,7 +350,7 @@ mod test {
         assert_eq!(res, vec![1, 3]);
 
         let mut res: Vec<i32> = root
-            .find_in_radius(&[0.5, 0.5, 0.5], 0.4)
+            .find_in_radius(&[0.5, 0.5, 0.5], 0.4, &Metric::Euclidean)
             .iter()
             .map(|p| p.data)
             .collect();
@@ -338,7 +358,7 @@ mod test {
         assert_eq!(res, vec![3]);
 
         let mut res: Vec<i32> = root
-            .find_in_radius(&[0.0, 0.75, 0.0], 0.3)
+            .find_in_radius(&[0.0, 0.75, 0.0], 0.3, &Metric::Euclidean)
             .iter()
             .map(|p| p.data)
             .collect();
@@ -346,11 +366,74 @@ mod test {
         assert_eq!(res, vec![4]);
 
         let mut res: Vec<i32> = root
-            .find_in_radius(&[0.5, 0.5, 0.5], 1.0)
+            .find_in_radius(&[0.5, 0.5, 0.5], 1.0, &Metric::Euclidean)
             .iter()
             .map(|p| p.data)
             .collect();
         res.sort();
         assert_eq!(res, vec![1, 2, 3, 4]);
     }
+
+    #[test]
+    fn find_in_ellipsoid() {
+        let mut root: NTree<i32, 2> = NTree::new(1);
+        assert!(root.insert(1, [0.5, 0.5]).is_ok());
+        assert!(root.insert(2, [0.8, 0.5]).is_ok());
+        assert!(root.insert(3, [0.5, 0.8]).is_ok());
+        assert!(root.insert(4, [0.7, 0.7]).is_ok());
+
+        let find = |ellipsoid: &Ellipsoid<2>| {
+            let mut res: Vec<i32> = root
+                .find_in_ellipsoid(ellipsoid)
+                .iter()
+                .map(|p| p.data)
+                .collect();
+            res.sort();
+            res
+        };
+        let wide = Ellipsoid::new(Loc::cartesian([0.5, 0.5]), [0.4, 0.1]);
+        assert_eq!(find(&wide), vec![1, 2]);
+        let tall = wide.with_rotation(Transform::rotation(0, 1, PI / 2.0));
+        assert_eq!(find(&tall), vec![1, 3]);
+        let diagonal = wide.with_rotation(Transform::rotation(0, 1, PI / 4.0));
+        assert_eq!(find(&diagonal), vec![1, 4]);
+    }
+
+    #[test]
+    fn find_by_metric() -> Result<(), TpnTreeError> {
+        let mut root: NTree<i32, 2> = NTree::new(1);
+        assert!(root.insert(1, [0.5, 0.5]).is_ok());
+        assert!(root.insert(2, [0.7, 0.5]).is_ok());
+        assert!(root.insert(3, [0.65, 0.65]).is_ok());
+        assert!(root.insert(4, [0.5, 0.9]).is_ok());
+
+        let find = |metric: &Metric<2>, radius: f64| {
+            let mut res: Vec<i32> = root
+                .find_in_radius(&[0.5, 0.5], radius, metric)
+                .iter()
+                .map(|p| p.data)
+                .collect();
+            res.sort();
+            res
+        };
+        assert_eq!(find(&Metric::Euclidean, 0.25), vec![1, 2, 3]);
+        assert_eq!(find(&Metric::Manhattan, 0.25), vec![1, 2]);
+        assert_eq!(find(&Metric::Chebyshev, 0.16), vec![1, 3]);
+        // counting vertical distance a quarter as much reaches further up
+        assert_eq!(
+            find(&Metric::WeightedEuclidean([1.0, 0.25]), 0.15),
+            vec![1, 4]
+        );
+        assert_eq!(
+            find(&Metric::WeightedEuclidean([1.0, 0.0]), 0.1),
+            vec![1, 4]
+        );
+
+        let closest = root.find_closest(&[0.6, 0.6], 0.5, &Metric::Manhattan)?;
+        assert_eq!(closest.unwrap().data, 3);
+        let closest =
+            root.find_closest(&[0.55, 0.8], 0.5, &Metric::WeightedEuclidean([0.1, 1.0]))?;
+        assert_eq!(closest.unwrap().data, 4);
+        Ok(())
+    }
 }
@@ -0,0 +1,373 @@
+use crate::{loc::Loc, LightArrangementError};
+
+/// Number of straight pieces each curved segment is measured with
+const CURVE_SUBDIVISIONS: usize = 64;
+
+/// A curve through N-dimensional space made of straight lines and cubic Bezier curves, for moving
+/// effects along and painting
+/// Positions along the path are given as `t` in 0..1 by arc length, so moving `t` at a steady
+/// rate moves at a steady speed no matter how the path is built
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path<const N: usize> {
+    segments: Vec<Segment<N>>,
+    /// (distance along the path, segment, position in segment) at points along every segment
+    /// Sorted by distance along the path
+    lengths: Vec<(f64, usize, f64)>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Segment<const N: usize> {
+    Line(Loc<N>, Loc<N>),
+    CubicBezier(Loc<N>, Loc<N>, Loc<N>, Loc<N>),
+}
+
+impl<const N: usize> Segment<N> {
+    fn point(&self, u: f64) -> Loc<N> {
+        match *self {
+            Segment::Line(a, b) => a.lerp(&b, u),
+            Segment::CubicBezier(p0, p1, p2, p3) => {
+                let v = 1.0 - u;
+                p0 * (v * v * v)
+                    + p1 * (3.0 * v * v * u)
+                    + p2 * (3.0 * v * u * u)
+                    + p3 * (u * u * u)
+            }
+        }
+    }
+
+    fn derivative(&self, u: f64) -> Loc<N> {
+        match *self {
+            Segment::Line(a, b) => b - a,
+            Segment::CubicBezier(p0, p1, p2, p3) => {
+                let v = 1.0 - u;
+                (p1 - p0) * (3.0 * v * v) + (p2 - p1) * (6.0 * v * u) + (p3 - p2) * (3.0 * u * u)
+            }
+        }
+    }
+
+    fn start(&self) -> Loc<N> {
+        self.point(0.0)
+    }
+
+    fn end(&self) -> Loc<N> {
+        self.point(1.0)
+    }
+
+    fn subdivisions(&self) -> usize {
+        match self {
+            Segment::Line(..) => 1,
+            Segment::CubicBezier(..) => CURVE_SUBDIVISIONS,
+        }
+    }
+}
+
+impl<const N: usize> Path<N> {
+    /// Straight line from `from` to `to`
+    pub fn line(from: Loc<N>, to: Loc<N>) -> Self {
+        Path::from_segments(vec![Segment::Line(from, to)])
+    }
+
+    /// Straight lines joining each of `points` to the next
+    /// Needs at least 2 points
+    pub fn polyline(points: &[Loc<N>]) -> Result<Self, LightArrangementError> {
+        if points.len() < 2 {
+            return Err(LightArrangementError::new(
+                "Polyline needs at least 2 points".to_string(),
+            ));
+        }
+        Ok(Path::from_segments(
+            points
+                .windows(2)
+                .map(|pair| Segment::Line(pair[0], pair[1]))
+                .collect(),
+        ))
+    }
+
+    /// Cubic Bezier curve from `start` to `end`, pulled toward `control1` and then `control2`
+    pub fn cubic_bezier(start: Loc<N>, control1: Loc<N>, control2: Loc<N>, end: Loc<N>) -> Self {
+        Path::from_segments(vec![Segment::CubicBezier(start, control1, control2, end)])
+    }
+
+    /// Smooth curve that passes through every one of `points` in order
+    /// The curve heads straight toward the second point at the start, and comes straight from the
+    /// second to last point at the end. Needs at least 2 points
+    pub fn catmull_rom(points: &[Loc<N>]) -> Result<Self, LightArrangementError> {
+        if points.len() < 2 {
+            return Err(LightArrangementError::new(
+                "Catmull-Rom spline needs at least 2 points".to_string(),
+            ));
+        }
+        let last = points.len() - 1;
+        let segments = (0..last)
+            .map(|i| {
+                let before = points[i.saturating_sub(1)];
+                let (start, end) = (points[i], points[i + 1]);
+                let after = points[(i + 2).min(last)];
+                Segment::CubicBezier(
+                    start,
+                    start + (end - before) * (1.0 / 6.0),
+                    end - (after - start) * (1.0 / 6.0),
+                    end,
+                )
+            })
+            .collect();
+        Ok(Path::from_segments(segments))
+    }
+
+    /// Returns the path that follows this one and then `other`, with a straight line joining
+    /// them if `other` does not start where this one ends
+    pub fn then(&self, other: &Path<N>) -> Self {
+        let mut segments = self.segments.clone();
+        let (end, start) = (self.end(), other.start());
+        if end != start {
+            segments.push(Segment::Line(end, start));
+        }
+        segments.extend(other.segments.iter().copied());
+        Path::from_segments(segments)
+    }
+
+    fn from_segments(segments: Vec<Segment<N>>) -> Self {
+        let mut lengths = vec![];
+        let mut length = 0.0;
+        for (index, segment) in segments.iter().enumerate() {
+            let subdivisions = segment.subdivisions();
+            let mut previous = segment.start();
+            lengths.push((length, index, 0.0));
+            for k in 1..=subdivisions {
+                let u = k as f64 / subdivisions as f64;
+                let point = segment.point(u);
+                length += previous.distance_to(&point);
+                lengths.push((length, index, u));
+                previous = point;
+            }
+        }
+        Path { segments, lengths }
+    }
+
+    /// Total length of the path
+    pub fn length(&self) -> f64 {
+        self.lengths.last().map_or(0.0, |(length, _, _)| *length)
+    }
+
+    pub fn start(&self) -> Loc<N> {
+        self.segments[0].start()
+    }
+
+    pub fn end(&self) -> Loc<N> {
+        self.segments[self.segments.len() - 1].end()
+    }
+
+    /// Returns the point `t` of the way along the path by arc length
+    /// `t` is clamped to 0..1
+    pub fn sample(&self, t: f64) -> Loc<N> {
+        let (segment, u) = self.locate(t);
+        self.segments[segment].point(u)
+    }
+
+    /// Returns the direction the path is heading `t` of the way along it, with length 1
+    /// `t` is clamped to 0..1
+    pub fn tangent(&self, t: f64) -> Loc<N> {
+        let (index, u) = self.locate(t);
+        let segment = &self.segments[index];
+        let tangent = segment.derivative(u);
+        if tangent.norm() > 1e-9 {
+            tangent.normalize()
+        } else {
+            // control points on top of an end point stop the curve there; use where it is going
+            let step = 1.0 / CURVE_SUBDIVISIONS as f64;
+            let (a, b) = if u < 0.5 {
+                (u, u + step)
+            } else {
+                (u - step, u)
+            };
+            (segment.point(b) - segment.point(a)).normalize()
+        }
+    }
+
+    /// Returns the shortest distance from `point` to the path
+    pub fn distance_to(&self, point: &Loc<N>) -> f64 {
+        self.closest(point).1
+    }
+
+    /// Returns `t` of the point on the path closest to `point`, and the distance to it
+    pub fn closest(&self, point: &Loc<N>) -> (f64, f64) {
+        let length = self.length();
+        let mut closest = (0.0, self.start().distance_to(point));
+        for pair in self.lengths.windows(2) {
+            let (start_length, start_segment, start_u) = pair[0];
+            let (end_length, _, end_u) = pair[1];
+            if end_length <= start_length {
+                continue;
+            }
+            let start = self.segments[start_segment].point(start_u);
+            let end = self.segments[start_segment].point(end_u);
+            let direction = end - start;
+            let amount =
+                ((*point - start).dot(&direction) / direction.dot(&direction)).clamp(0.0, 1.0);
+            let distance = start.lerp(&end, amount).distance_to(point);
+            if distance < closest.1 {
+                let along = start_length + (end_length - start_length) * amount;
+                closest = (if length > 0.0 { along / length } else { 0.0 }, distance);
+            }
+        }
+        closest
+    }
+
+    /// Returns the corners of an axis aligned box holding the whole path
+    pub fn bounding_box(&self) -> (Loc<N>, Loc<N>) {
+        let mut lower = self.start();
+        let mut upper = lower;
+        for segment in self.segments.iter() {
+            let points = match *segment {
+                Segment::Line(a, b) => vec![a, b],
+                // a Bezier curve stays inside the hull of its control points
+                Segment::CubicBezier(p0, p1, p2, p3) => vec![p0, p1, p2, p3],
+            };
+            for point in points {
+                lower = lower.min(&point);
+                upper = upper.max(&point);
+            }
+        }
+        (lower, upper)
+    }
+
+    /// Returns the segment and position in it `t` of the way along the path
+    fn locate(&self, t: f64) -> (usize, f64) {
+        let target = t.clamp(0.0, 1.0) * self.length();
+        let upper = self
+            .lengths
+            .partition_point(|(length, _, _)| *length <= target);
+        if upper == 0 {
+            return (self.lengths[0].1, self.lengths[0].2);
+        }
+        if upper == self.lengths.len() {
+            let (_, segment, u) = self.lengths[upper - 1];
+            return (segment, u);
+        }
+
+        let (start_length, start_segment, start_u) = self.lengths[upper - 1];
+        let (end_length, end_segment, end_u) = self.lengths[upper];
+        if start_segment != end_segment {
+            return (start_segment, start_u);
+        }
+        let amount = (target - start_length) / (end_length - start_length);
+        (start_segment, start_u + (end_u - start_u) * amount)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn approx<const N: usize>(loc: &Loc<N>, coords: [f64; N]) -> bool {
+        loc.distance_to(&Loc::cartesian(coords)) < 0.001
+    }
+
+    #[test]
+    fn line() {
+        let path = Path::line(Loc::cartesian([0.0, 0.0]), Loc::cartesian([1.0, 0.0]));
+        assert!((path.length() - 1.0).abs() < 0.001);
+        assert!(approx(&path.sample(0.0), [0.0, 0.0]));
+        assert!(approx(&path.sample(0.25), [0.25, 0.0]));
+        assert!(approx(&path.sample(1.0), [1.0, 0.0]));
+        assert!(approx(&path.sample(2.0), [1.0, 0.0]));
+        assert!(approx(&path.tangent(0.5), [1.0, 0.0]));
+    }
+
+    #[test]
+    fn polyline() -> Result<(), LightArrangementError> {
+        assert!(Path::polyline(&[Loc::cartesian([0.0, 0.0])]).is_err());
+
+        // one long and one short segment, sampled evenly by length
+        let path = Path::polyline(&[
+            Loc::cartesian([0.0, 0.0]),
+            Loc::cartesian([0.75, 0.0]),
+            Loc::cartesian([0.75, 0.25]),
+        ])?;
+        assert!((path.length() - 1.0).abs() < 0.001);
+        assert!(approx(&path.sample(0.5), [0.5, 0.0]));
+        assert!(approx(&path.sample(0.75), [0.75, 0.0]));
+        assert!(approx(&path.sample(0.875), [0.75, 0.125]));
+        assert!(approx(&path.tangent(0.2), [1.0, 0.0]));
+        assert!(approx(&path.tangent(0.9), [0.0, 1.0]));
+        Ok(())
+    }
+
+    #[test]
+    fn cubic_bezier() {
+        let path = Path::cubic_bezier(
+            Loc::cartesian([0.0, 0.0]),
+            Loc::cartesian([0.0, 1.0]),
+            Loc::cartesian([1.0, 1.0]),
+            Loc::cartesian([1.0, 0.0]),
+        );
+        assert!(approx(&path.sample(0.0), [0.0, 0.0]));
+        assert!(approx(&path.sample(0.5), [0.5, 0.75]));
+        assert!(approx(&path.sample(1.0), [1.0, 0.0]));
+        assert!(approx(&path.tangent(0.0), [0.0, 1.0]));
+        assert!(approx(&path.tangent(0.5), [1.0, 0.0]));
+        assert!(approx(&path.tangent(1.0), [0.0, -1.0]));
+
+        // even spacing by arc length, even though the curve is faster in the middle
+        let steps: Vec<f64> = (0..10)
+            .map(|i| {
+                path.sample(i as f64 / 10.0)
+                    .distance_to(&path.sample((i + 1) as f64 / 10.0))
+            })
+            .collect();
+        for step in steps.iter() {
+            assert!((step - path.length() / 10.0).abs() < 0.005);
+        }
+
+        // control points on the end points still give a direction
+        let path = Path::cubic_bezier(
+            Loc::cartesian([0.0, 0.0]),
+            Loc::cartesian([0.0, 0.0]),
+            Loc::cartesian([1.0, 0.0]),
+            Loc::cartesian([1.0, 0.0]),
+        );
+        assert!(approx(&path.tangent(0.0), [1.0, 0.0]));
+    }
+
+    #[test]
+    fn catmull_rom() -> Result<(), LightArrangementError> {
+        let points = [
+            Loc::cartesian([0.0, 0.0, 0.0]),
+            Loc::cartesian([0.5, 0.5, 0.0]),
+            Loc::cartesian([1.0, 0.0, 0.5]),
+            Loc::cartesian([1.0, 1.0, 1.0]),
+        ];
+        let path = Path::catmull_rom(&points)?;
+        assert!(approx(&path.sample(0.0), [0.0, 0.0, 0.0]));
+        assert!(approx(&path.sample(1.0), [1.0, 1.0, 1.0]));
+        for point in points.iter() {
+            assert!(path.distance_to(point) < 0.001);
+        }
+
+        // the curve through the middle point runs parallel to the line joining its neighbors
+        let (t, _) = path.closest(&points[1]);
+        let tangent = path.tangent(t);
+        let expected = (points[2] - points[0]).normalize();
+        assert!(tangent.distance_to(&expected) < 0.01);
+
+        assert!(Path::catmull_rom(&points[..1]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn join_and_measure() {
+        let path = Path::line(Loc::cartesian([0.0, 0.0]), Loc::cartesian([0.5, 0.0])).then(
+            &Path::line(Loc::cartesian([0.5, 0.5]), Loc::cartesian([0.0, 0.5])),
+        );
+        assert!((path.length() - 1.5).abs() < 0.001);
+        assert!(approx(&path.sample(0.5), [0.5, 0.25]));
+
+        let (t, distance) = path.closest(&Loc::cartesian([0.25, 0.1]));
+        assert!((t - 0.25 / 1.5).abs() < 0.001);
+        assert!((distance - 0.1).abs() < 0.001);
+
+        let (lower, upper) = path.bounding_box();
+        assert!(approx(&lower, [0.0, 0.0]));
+        assert!(approx(&upper, [0.5, 0.5]));
+    }
+}
@@ -0,0 +1,254 @@
+use super::Shape;
+use crate::loc::Loc;
+
+/// Everything inside either of two shapes
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Union<A, B> {
+    a: A,
+    b: B,
+}
+
+impl<A, B> Union<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Union { a, b }
+    }
+}
+
+impl<const N: usize, A: Shape<N>, B: Shape<N>> Shape<N> for Union<A, B> {
+    fn signed_distance(&self, loc: &Loc<N>) -> f64 {
+        self.a.signed_distance(loc).min(self.b.signed_distance(loc))
+    }
+
+    fn bounding_box(&self) -> (Loc<N>, Loc<N>) {
+        let (a_lower, a_upper) = self.a.bounding_box();
+        let (b_lower, b_upper) = self.b.bounding_box();
+        (a_lower.min(&b_lower), a_upper.max(&b_upper))
+    }
+}
+
+/// Everything inside both of two shapes
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Intersection<A, B> {
+    a: A,
+    b: B,
+}
+
+impl<A, B> Intersection<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Intersection { a, b }
+    }
+}
+
+impl<const N: usize, A: Shape<N>, B: Shape<N>> Shape<N> for Intersection<A, B> {
+    fn signed_distance(&self, loc: &Loc<N>) -> f64 {
+        self.a.signed_distance(loc).max(self.b.signed_distance(loc))
+    }
+
+    /// Overlap of the bounding boxes, where a lower corner above the upper one means the shapes
+    /// cannot meet
+    fn bounding_box(&self) -> (Loc<N>, Loc<N>) {
+        let (a_lower, a_upper) = self.a.bounding_box();
+        let (b_lower, b_upper) = self.b.bounding_box();
+        (a_lower.max(&b_lower), a_upper.min(&b_upper))
+    }
+}
+
+/// Everything inside the first shape and outside the second
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Difference<A, B> {
+    a: A,
+    b: B,
+}
+
+impl<A, B> Difference<A, B> {
+    /// Creates the shape of `a` with `b` cut out of it
+    pub fn new(a: A, b: B) -> Self {
+        Difference { a, b }
+    }
+}
+
+impl<const N: usize, A: Shape<N>, B: Shape<N>> Shape<N> for Difference<A, B> {
+    fn signed_distance(&self, loc: &Loc<N>) -> f64 {
+        self.a
+            .signed_distance(loc)
+            .max(-self.b.signed_distance(loc))
+    }
+
+    fn bounding_box(&self) -> (Loc<N>, Loc<N>) {
+        self.a.bounding_box()
+    }
+}
+
+/// Union of two shapes that melts them together where they come within `radius` of each other
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SmoothUnion<A, B> {
+    a: A,
+    b: B,
+    radius: f64,
+}
+
+impl<A, B> SmoothUnion<A, B> {
+    pub fn new(a: A, b: B, radius: f64) -> Self {
+        SmoothUnion {
+            a,
+            b,
+            radius: radius.max(0.0),
+        }
+    }
+}
+
+impl<const N: usize, A: Shape<N>, B: Shape<N>> Shape<N> for SmoothUnion<A, B> {
+    fn signed_distance(&self, loc: &Loc<N>) -> f64 {
+        let (a, b) = (self.a.signed_distance(loc), self.b.signed_distance(loc));
+        if self.radius == 0.0 {
+            return a.min(b);
+        }
+        let h = (0.5 + 0.5 * (b - a) / self.radius).clamp(0.0, 1.0);
+        b + (a - b) * h - self.radius * h * (1.0 - h)
+    }
+
+    /// The melted part bulges out at most a quarter of `radius` past either shape
+    fn bounding_box(&self) -> (Loc<N>, Loc<N>) {
+        let (a_lower, a_upper) = self.a.bounding_box();
+        let (b_lower, b_upper) = self.b.bounding_box();
+        let bulge = Loc::cartesian([self.radius / 4.0; N]);
+        (a_lower.min(&b_lower) - bulge, a_upper.max(&b_upper) + bulge)
+    }
+}
+
+/// The first shape with the second cut out of it, rounding off the cut edges over `radius`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SmoothSubtraction<A, B> {
+    a: A,
+    b: B,
+    radius: f64,
+}
+
+impl<A, B> SmoothSubtraction<A, B> {
+    /// Creates the shape of `a` with `b` cut out of it
+    pub fn new(a: A, b: B, radius: f64) -> Self {
+        SmoothSubtraction {
+            a,
+            b,
+            radius: radius.max(0.0),
+        }
+    }
+}
+
+impl<const N: usize, A: Shape<N>, B: Shape<N>> Shape<N> for SmoothSubtraction<A, B> {
+    fn signed_distance(&self, loc: &Loc<N>) -> f64 {
+        let (a, b) = (self.a.signed_distance(loc), -self.b.signed_distance(loc));
+        if self.radius == 0.0 {
+            return a.max(b);
+        }
+        let h = (0.5 - 0.5 * (b - a) / self.radius).clamp(0.0, 1.0);
+        b + (a - b) * h + self.radius * h * (1.0 - h)
+    }
+
+    /// Rounding only takes away from the first shape
+    fn bounding_box(&self) -> (Loc<N>, Loc<N>) {
+        self.a.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shape::{OrientedBox, Sphere};
+
+    fn approx(a: f64, b: f64) -> bool {
+        (a - b).abs() < 0.0001
+    }
+
+    fn approx_loc<const N: usize>(loc: &Loc<N>, coords: [f64; N]) -> bool {
+        loc.distance_to(&Loc::cartesian(coords)) < 0.0001
+    }
+
+    fn two_spheres() -> (Sphere<2>, Sphere<2>) {
+        (
+            Sphere::new(Loc::cartesian([0.3, 0.5]), 0.2),
+            Sphere::new(Loc::cartesian([0.6, 0.5]), 0.2),
+        )
+    }
+
+    #[test]
+    fn union_and_intersection() {
+        let (a, b) = two_spheres();
+        let union = a.union(b);
+        assert!(union.contains(&Loc::cartesian([0.15, 0.5])));
+        assert!(union.contains(&Loc::cartesian([0.75, 0.5])));
+        assert!(!union.contains(&Loc::cartesian([0.45, 0.7])));
+        assert!(approx(
+            union.signed_distance(&Loc::cartesian([0.0, 0.5])),
+            0.1
+        ));
+        let (lower, upper) = union.bounding_box();
+        assert!(approx_loc(&lower, [0.1, 0.3]));
+        assert!(approx_loc(&upper, [0.8, 0.7]));
+
+        let intersection = a.intersection(b);
+        assert!(intersection.contains(&Loc::cartesian([0.45, 0.5])));
+        assert!(!intersection.contains(&Loc::cartesian([0.3, 0.5])));
+        assert!(!intersection.contains(&Loc::cartesian([0.6, 0.5])));
+        let (lower, upper) = intersection.bounding_box();
+        assert!(approx_loc(&lower, [0.4, 0.3]));
+        assert!(approx_loc(&upper, [0.5, 0.7]));
+    }
+
+    #[test]
+    fn difference() {
+        // hollow shell of a sphere
+        let center = Loc::cartesian([0.5, 0.5, 0.5]);
+        let shell = Sphere::new(center, 0.4).difference(Sphere::new(center, 0.3));
+        assert!(!shell.contains(&center));
+        assert!(shell.contains(&Loc::cartesian([0.85, 0.5, 0.5])));
+        assert!(!shell.contains(&Loc::cartesian([0.95, 0.5, 0.5])));
+        assert!(approx(shell.signed_distance(&center), 0.3));
+        let (lower, upper) = shell.bounding_box();
+        assert!(approx_loc(&lower, [0.1, 0.1, 0.1]));
+        assert!(approx_loc(&upper, [0.9, 0.9, 0.9]));
+
+        // box with a bite out of its corner
+        let bitten = OrientedBox::new(Loc::cartesian([0.5, 0.5]), [0.2, 0.2])
+            .difference(Sphere::new(Loc::cartesian([0.7, 0.7]), 0.15));
+        assert!(bitten.contains(&Loc::cartesian([0.5, 0.5])));
+        assert!(bitten.contains(&Loc::cartesian([0.35, 0.65])));
+        assert!(!bitten.contains(&Loc::cartesian([0.65, 0.65])));
+    }
+
+    #[test]
+    fn smooth() {
+        let (a, b) = two_spheres();
+        let sharp = a.union(b);
+        let smooth = a.smooth_union(b, 0.1);
+        // the same away from where they meet, and filled in where they do
+        let far = Loc::cartesian([0.0, 0.5]);
+        assert!(approx(
+            smooth.signed_distance(&far),
+            sharp.signed_distance(&far)
+        ));
+        let seam = Loc::cartesian([0.45, 0.64]);
+        assert!(!sharp.contains(&seam));
+        assert!(smooth.contains(&seam));
+        assert_eq!(
+            a.smooth_union(b, 0.0).signed_distance(&seam),
+            sharp.signed_distance(&seam)
+        );
+        let (lower, upper) = smooth.bounding_box();
+        assert!(approx_loc(&lower, [0.075, 0.275]));
+        assert!(approx_loc(&upper, [0.825, 0.725]));
+
+        let sharp = a.difference(b);
+        let smooth = a.smooth_subtraction(b, 0.1);
+        let far = Loc::cartesian([0.15, 0.5]);
+        assert!(approx(
+            smooth.signed_distance(&far),
+            sharp.signed_distance(&far)
+        ));
+        // rounding off the cut takes a little more away near its edges
+        let edge = Loc::cartesian([0.43, 0.63]);
+        assert!(sharp.contains(&edge));
+        assert!(!smooth.contains(&edge));
+        assert_eq!(smooth.bounding_box(), a.bounding_box());
+    }
+}
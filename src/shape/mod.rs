@@ -0,0 +1,88 @@
+/// Shapes described by signed distance functions, for lighting up any region of space
+mod csg;
+mod polytope;
+mod primitives;
+
+pub use csg::{Difference, Intersection, SmoothSubtraction, SmoothUnion, Union};
+pub use polytope::{ConvexHull, Polygon};
+pub use primitives::{Capsule, Cone, Cylinder, Ellipsoid, OrientedBox, Plane, Sphere, Torus};
+
+use crate::loc::Loc;
+
+/// A region of N-dimensional space described by its signed distance function
+pub trait Shape<const N: usize> {
+    /// Returns the distance from `loc` to the surface of the shape, which is negative inside it
+    /// Shapes should never return more than the true distance, so lights a distance `d` away can
+    /// be skipped by anything that only looks within `d`
+    fn signed_distance(&self, loc: &Loc<N>) -> f64;
+
+    /// Returns the lower and upper corners of an axis aligned box holding all of the shape
+    /// Shapes that go on forever have infinite corners
+    fn bounding_box(&self) -> (Loc<N>, Loc<N>);
+
+    /// True if `loc` is inside the shape or on its surface
+    fn contains(&self, loc: &Loc<N>) -> bool {
+        self.signed_distance(loc) <= 0.0
+    }
+
+    /// Everything inside this shape or `other`
+    fn union<B: Shape<N>>(self, other: B) -> Union<Self, B>
+    where
+        Self: Sized,
+    {
+        Union::new(self, other)
+    }
+
+    /// Everything inside both this shape and `other`
+    fn intersection<B: Shape<N>>(self, other: B) -> Intersection<Self, B>
+    where
+        Self: Sized,
+    {
+        Intersection::new(self, other)
+    }
+
+    /// This shape with `other` cut out of it
+    fn difference<B: Shape<N>>(self, other: B) -> Difference<Self, B>
+    where
+        Self: Sized,
+    {
+        Difference::new(self, other)
+    }
+
+    /// Everything inside this shape or `other`, melted together where they come within `radius`
+    fn smooth_union<B: Shape<N>>(self, other: B, radius: f64) -> SmoothUnion<Self, B>
+    where
+        Self: Sized,
+    {
+        SmoothUnion::new(self, other, radius)
+    }
+
+    /// This shape with `other` cut out of it, with the cut edges rounded off over `radius`
+    fn smooth_subtraction<B: Shape<N>>(self, other: B, radius: f64) -> SmoothSubtraction<Self, B>
+    where
+        Self: Sized,
+    {
+        SmoothSubtraction::new(self, other, radius)
+    }
+}
+
+/// Lets shapes of different types be combined or stored together as `Box<dyn Shape<N>>`
+impl<const N: usize, S: Shape<N> + ?Sized> Shape<N> for Box<S> {
+    fn signed_distance(&self, loc: &Loc<N>) -> f64 {
+        self.as_ref().signed_distance(loc)
+    }
+
+    fn bounding_box(&self) -> (Loc<N>, Loc<N>) {
+        self.as_ref().bounding_box()
+    }
+}
+
+impl<const N: usize, S: Shape<N> + ?Sized> Shape<N> for &S {
+    fn signed_distance(&self, loc: &Loc<N>) -> f64 {
+        (**self).signed_distance(loc)
+    }
+
+    fn bounding_box(&self) -> (Loc<N>, Loc<N>) {
+        (**self).bounding_box()
+    }
+}
@@ -0,0 +1,432 @@
+use super::{Plane, Shape};
+use crate::{loc::Loc, transform::Transform, LightArrangementError};
+
+/// How far a point can be past a face and still count as on it, for rounding error
+const TOLERANCE: f64 = 1e-9;
+
+/// Half the size of the box unbounded hulls are cut down to when looking for their corners
+/// Corners on it mean the hull goes on forever that way
+const FAR: f64 = 1e6;
+
+/// Convex region bounded by flat faces, like a triangle, tetrahedron or any other convex polytope
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConvexHull<const N: usize> {
+    /// Every face, with the inside facing away from its normal
+    faces: Vec<Plane<N>>,
+    lower: Loc<N>,
+    upper: Loc<N>,
+}
+
+impl<const N: usize> ConvexHull<N> {
+    /// Creates the smallest convex region holding every point in `vertices`
+    /// Errors if the vertices all lie in a line, plane or other flat of fewer than N dimensions
+    pub fn from_vertices(vertices: &[Loc<N>]) -> Result<Self, LightArrangementError> {
+        if vertices.len() <= N {
+            return Err(LightArrangementError::new(format!(
+                "Convex hull in {} dimensions needs at least {} vertices but got {}",
+                N,
+                N + 1,
+                vertices.len()
+            )));
+        }
+
+        // try the plane through every N vertices, keeping those with all vertices on one side
+        let mut faces: Vec<Plane<N>> = vec![];
+        let mut flat = false;
+        for_each_combination(vertices.len(), N, &mut |chosen| {
+            let normal =
+                match normal_through(&chosen.iter().map(|i| vertices[*i]).collect::<Vec<_>>()) {
+                    Some(normal) => normal,
+                    None => return,
+                };
+            let plane = Plane::through(&vertices[chosen[0]], normal);
+            let distances: Vec<f64> = vertices.iter().map(|v| plane.signed_distance(v)).collect();
+            let below = distances.iter().all(|d| *d <= TOLERANCE);
+            let above = distances.iter().all(|d| *d >= -TOLERANCE);
+            let face = match (below, above) {
+                (true, true) => {
+                    flat = true;
+                    return;
+                }
+                (true, false) => plane,
+                (false, true) => Plane::new(-plane.normal(), -plane.offset()),
+                (false, false) => return,
+            };
+            if !faces.iter().any(|f| same_plane(f, &face)) {
+                faces.push(face);
+            }
+        });
+        if flat || faces.is_empty() {
+            return Err(LightArrangementError::new(format!(
+                "Vertices of convex hull do not span {} dimensions",
+                N
+            )));
+        }
+
+        let mut lower = vertices[0];
+        let mut upper = vertices[0];
+        for vertex in vertices {
+            lower = lower.min(vertex);
+            upper = upper.max(vertex);
+        }
+        Ok(ConvexHull {
+            faces,
+            lower,
+            upper,
+        })
+    }
+
+    /// Creates the region of points meeting every inequality `normal · point <= offset` in
+    /// `half_spaces`. The region can go on forever or be empty
+    pub fn from_half_spaces(half_spaces: &[(Loc<N>, f64)]) -> Self {
+        let faces: Vec<Plane<N>> = half_spaces
+            .iter()
+            .map(|(normal, offset)| Plane::new(*normal, *offset))
+            .collect();
+
+        // corners are where N faces meet, cut down by a far away box to find unbounded directions
+        let mut planes = faces.clone();
+        for i in 0..N {
+            let mut axis = [0.0; N];
+            axis[i] = 1.0;
+            planes.push(Plane::new(Loc::cartesian(axis), FAR));
+            axis[i] = -1.0;
+            planes.push(Plane::new(Loc::cartesian(axis), FAR));
+        }
+        let mut lower = Loc::cartesian([f64::INFINITY; N]);
+        let mut upper = Loc::cartesian([f64::NEG_INFINITY; N]);
+        for_each_combination(planes.len(), N, &mut |chosen| {
+            let mut matrix = [[0.0; N]; N];
+            let mut offsets = [0.0; N];
+            for (row, i) in chosen.iter().enumerate() {
+                matrix[row] = planes[*i].normal().coords;
+                offsets[row] = planes[*i].offset();
+            }
+            let corner = match Transform::from_matrix(matrix, [0.0; N]).inverse() {
+                Some(inverse) => inverse.apply(&Loc::cartesian(offsets)),
+                None => return,
+            };
+            let scale = TOLERANCE * corner.norm().max(1.0);
+            if planes.iter().all(|p| p.signed_distance(&corner) <= scale) {
+                lower = lower.min(&corner);
+                upper = upper.max(&corner);
+            }
+        });
+        for i in 0..N {
+            if lower.coords[i] <= -FAR * (1.0 - TOLERANCE) {
+                lower.coords[i] = f64::NEG_INFINITY;
+            }
+            if upper.coords[i] >= FAR * (1.0 - TOLERANCE) {
+                upper.coords[i] = f64::INFINITY;
+            }
+        }
+        ConvexHull {
+            faces,
+            lower,
+            upper,
+        }
+    }
+}
+
+impl<const N: usize> Shape<N> for ConvexHull<N> {
+    /// Distance past the furthest face, which is exact inside and can be less than the true
+    /// distance outside near corners
+    fn signed_distance(&self, loc: &Loc<N>) -> f64 {
+        self.faces
+            .iter()
+            .map(|face| face.signed_distance(loc))
+            .fold(f64::NEG_INFINITY, f64::max)
+    }
+
+    /// Box around the corners, where a lower corner above the upper one means the hull is empty
+    fn bounding_box(&self) -> (Loc<N>, Loc<N>) {
+        (self.lower, self.upper)
+    }
+}
+
+/// Flat 2D region inside a closed loop of vertices, which can be any shape including ones that
+/// cross themselves. Points are inside if a ray from them crosses the loop an odd number of times,
+/// so the middle of a five pointed star drawn in one stroke is left out
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    vertices: Vec<Loc<2>>,
+    lower: Loc<2>,
+    upper: Loc<2>,
+}
+
+impl Polygon {
+    /// Creates the polygon with edges from each vertex to the next, and from the last back to the
+    /// first. Errors if there are fewer than 3 vertices
+    pub fn new(vertices: &[Loc<2>]) -> Result<Self, LightArrangementError> {
+        if vertices.len() < 3 {
+            return Err(LightArrangementError::new(format!(
+                "Polygon needs at least 3 vertices but got {}",
+                vertices.len()
+            )));
+        }
+        let mut lower = vertices[0];
+        let mut upper = vertices[0];
+        for vertex in vertices {
+            lower = lower.min(vertex);
+            upper = upper.max(vertex);
+        }
+        Ok(Polygon {
+            vertices: vertices.to_vec(),
+            lower,
+            upper,
+        })
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (&Loc<2>, &Loc<2>)> {
+        self.vertices
+            .iter()
+            .zip(self.vertices.iter().cycle().skip(1))
+    }
+}
+
+impl Shape<2> for Polygon {
+    fn signed_distance(&self, loc: &Loc<2>) -> f64 {
+        let [x, y] = loc.coords;
+        let mut closest = f64::INFINITY;
+        let mut inside = false;
+        for (a, b) in self.edges() {
+            let edge = *b - *a;
+            let length = edge.dot(&edge);
+            let t = if length > 0.0 {
+                ((*loc - *a).dot(&edge) / length).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            closest = closest.min(loc.distance_to(&a.lerp(b, t)));
+
+            let ([ax, ay], [bx, by]) = (a.coords, b.coords);
+            if (ay > y) != (by > y) && x < ax + (y - ay) * (bx - ax) / (by - ay) {
+                inside = !inside;
+            }
+        }
+        if inside {
+            -closest
+        } else {
+            closest
+        }
+    }
+
+    fn bounding_box(&self) -> (Loc<2>, Loc<2>) {
+        (self.lower, self.upper)
+    }
+}
+
+/// Calls `f` with every way of choosing `k` of the indices below `n`, in increasing order
+fn for_each_combination(n: usize, k: usize, f: &mut dyn FnMut(&[usize])) {
+    if k > n {
+        return;
+    }
+    let mut chosen: Vec<usize> = (0..k).collect();
+    loop {
+        f(&chosen);
+        // move the last index that can still go up, and put the ones after it right behind it
+        let mut i = k;
+        while i > 0 && chosen[i - 1] == n - k + i - 1 {
+            i -= 1;
+        }
+        if i == 0 {
+            return;
+        }
+        chosen[i - 1] += 1;
+        for j in i..k {
+            chosen[j] = chosen[j - 1] + 1;
+        }
+    }
+}
+
+/// Returns a normal to the plane through `points`, which should hold N points, or `None` if they
+/// do not pin down a single plane
+fn normal_through<const N: usize>(points: &[Loc<N>]) -> Option<Loc<N>> {
+    // solve for a normal at right angles to every edge from the first point, trying each axis as
+    // the one it must have some of
+    (0..N).find_map(|axis| {
+        let mut matrix = [[0.0; N]; N];
+        for (row, point) in points.iter().skip(1).enumerate() {
+            matrix[row] = (*point - points[0]).coords;
+        }
+        matrix[N - 1][axis] = 1.0;
+        let mut target = [0.0; N];
+        target[N - 1] = 1.0;
+        let inverse = Transform::from_matrix(matrix, [0.0; N]).inverse()?;
+        Some(inverse.apply(&Loc::cartesian(target)))
+    })
+}
+
+fn same_plane<const N: usize>(a: &Plane<N>, b: &Plane<N>) -> bool {
+    a.normal().distance_to(&b.normal()) < TOLERANCE && (a.offset() - b.offset()).abs() < TOLERANCE
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    fn approx(a: f64, b: f64) -> bool {
+        (a - b).abs() < 0.0001
+    }
+
+    fn approx_loc<const N: usize>(loc: &Loc<N>, coords: [f64; N]) -> bool {
+        loc.distance_to(&Loc::cartesian(coords)) < 0.0001
+    }
+
+    fn triangle() -> Vec<Loc<2>> {
+        vec![
+            Loc::cartesian([0.1, 0.1]),
+            Loc::cartesian([0.9, 0.1]),
+            Loc::cartesian([0.1, 0.9]),
+        ]
+    }
+
+    #[test]
+    fn hull_from_vertices() -> Result<(), LightArrangementError> {
+        // an extra point inside does not change the hull
+        let mut vertices = triangle();
+        vertices.push(Loc::cartesian([0.3, 0.3]));
+        let hull = ConvexHull::from_vertices(&vertices)?;
+        assert!(hull.contains(&Loc::cartesian([0.3, 0.3])));
+        assert!(hull.contains(&Loc::cartesian([0.45, 0.45])));
+        assert!(!hull.contains(&Loc::cartesian([0.55, 0.55])));
+        assert!(approx(
+            hull.signed_distance(&Loc::cartesian([0.2, 0.5])),
+            -0.1
+        ));
+        assert!(approx(
+            hull.signed_distance(&Loc::cartesian([0.5, 0.0])),
+            0.1
+        ));
+        let (lower, upper) = hull.bounding_box();
+        assert!(approx_loc(&lower, [0.1, 0.1]));
+        assert!(approx_loc(&upper, [0.9, 0.9]));
+
+        let tetrahedron = ConvexHull::from_vertices(&[
+            Loc::cartesian([0.0, 0.0, 0.0]),
+            Loc::cartesian([1.0, 0.0, 0.0]),
+            Loc::cartesian([0.0, 1.0, 0.0]),
+            Loc::cartesian([0.0, 0.0, 1.0]),
+        ])?;
+        assert!(tetrahedron.contains(&Loc::cartesian([0.2, 0.2, 0.2])));
+        assert!(!tetrahedron.contains(&Loc::cartesian([0.4, 0.4, 0.4])));
+        assert!(!tetrahedron.contains(&Loc::cartesian([0.2, -0.1, 0.2])));
+        assert_eq!(tetrahedron.faces.len(), 4);
+
+        let cube: Vec<Loc<3>> = (0..8)
+            .map(|i| Loc::cartesian([(i & 1) as f64, ((i >> 1) & 1) as f64, (i >> 2) as f64]))
+            .collect();
+        let cube = ConvexHull::from_vertices(&cube)?;
+        assert_eq!(cube.faces.len(), 6);
+        assert!(approx(
+            cube.signed_distance(&Loc::cartesian([0.5, 0.5, 0.5])),
+            -0.5
+        ));
+
+        assert!(ConvexHull::from_vertices(&triangle()[..2]).is_err());
+        let line = [[0.1, 0.1], [0.5, 0.5], [0.9, 0.9]].map(Loc::cartesian);
+        assert!(ConvexHull::from_vertices(&line).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn hull_from_half_spaces() -> Result<(), LightArrangementError> {
+        let from_vertices = ConvexHull::from_vertices(&triangle())?;
+        let hull = ConvexHull::from_half_spaces(&[
+            (Loc::cartesian([0.0, -1.0]), -0.1),
+            (Loc::cartesian([-1.0, 0.0]), -0.1),
+            (Loc::cartesian([1.0, 1.0]), 1.0),
+        ]);
+        for point in [[0.3, 0.3], [0.2, 0.5], [0.5, 0.0], [0.6, 0.6], [1.0, 1.0]] {
+            let point = Loc::cartesian(point);
+            assert!(approx(
+                hull.signed_distance(&point),
+                from_vertices.signed_distance(&point)
+            ));
+        }
+        let (lower, upper) = hull.bounding_box();
+        assert!(approx_loc(&lower, [0.1, 0.1]));
+        assert!(approx_loc(&upper, [0.9, 0.9]));
+
+        // a wedge open toward positive x
+        let wedge = ConvexHull::from_half_spaces(&[
+            (Loc::cartesian([-1.0, 1.0]), 0.0),
+            (Loc::cartesian([-1.0, -1.0]), 0.0),
+        ]);
+        assert!(wedge.contains(&Loc::cartesian([5.0, 1.0])));
+        assert!(!wedge.contains(&Loc::cartesian([-0.1, 0.0])));
+        let (lower, upper) = wedge.bounding_box();
+        assert_eq!(lower.coords, [0.0, f64::NEG_INFINITY]);
+        assert_eq!(upper.coords, [f64::INFINITY; 2]);
+
+        // nothing is both left of 0.2 and right of 0.4
+        let empty = ConvexHull::from_half_spaces(&[
+            (Loc::cartesian([1.0, 0.0]), 0.2),
+            (Loc::cartesian([-1.0, 0.0]), -0.4),
+        ]);
+        let (lower, upper) = empty.bounding_box();
+        assert!(lower.coords[0] > upper.coords[0]);
+        Ok(())
+    }
+
+    #[test]
+    fn polygon() -> Result<(), LightArrangementError> {
+        // an L with the top right corner missing
+        let l = Polygon::new(&[
+            Loc::cartesian([0.1, 0.1]),
+            Loc::cartesian([0.9, 0.1]),
+            Loc::cartesian([0.9, 0.5]),
+            Loc::cartesian([0.5, 0.5]),
+            Loc::cartesian([0.5, 0.9]),
+            Loc::cartesian([0.1, 0.9]),
+        ])?;
+        assert!(l.contains(&Loc::cartesian([0.3, 0.7])));
+        assert!(l.contains(&Loc::cartesian([0.7, 0.3])));
+        assert!(!l.contains(&Loc::cartesian([0.7, 0.7])));
+        assert!(approx(l.signed_distance(&Loc::cartesian([0.7, 0.7])), 0.2));
+        assert!(approx(l.signed_distance(&Loc::cartesian([0.2, 0.2])), -0.1));
+        let (lower, upper) = l.bounding_box();
+        assert!(approx_loc(&lower, [0.1, 0.1]));
+        assert!(approx_loc(&upper, [0.9, 0.9]));
+
+        // a five pointed star drawn in one stroke has a hole in the middle
+        let points: Vec<Loc<2>> = (0..5)
+            .map(|i| {
+                Loc::polar(
+                    0.4,
+                    &vec![PI / 2.0 + i as f64 * 4.0 * PI / 5.0],
+                    &[0.5, 0.5],
+                )
+            })
+            .collect();
+        let star = Polygon::new(&points)?;
+        assert!(!star.contains(&Loc::cartesian([0.5, 0.5])));
+        assert!(star.contains(&Loc::cartesian([0.5, 0.8])));
+        assert!(!star.contains(&Loc::cartesian([0.5, 0.95])));
+
+        assert!(Polygon::new(&triangle()[..2]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn combinations() {
+        let mut all = vec![];
+        for_each_combination(4, 2, &mut |chosen| all.push(chosen.to_vec()));
+        assert_eq!(
+            all,
+            vec![
+                vec![0, 1],
+                vec![0, 2],
+                vec![0, 3],
+                vec![1, 2],
+                vec![1, 3],
+                vec![2, 3]
+            ]
+        );
+        let mut count = 0;
+        for_each_combination(2, 3, &mut |_| count += 1);
+        assert_eq!(count, 0);
+    }
+}
@@ -0,0 +1,767 @@
+use super::Shape;
+use crate::{loc::Loc, transform::Transform};
+
+/// Every point within `radius` of `center`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Sphere<const N: usize> {
+    center: Loc<N>,
+    radius: f64,
+}
+
+impl<const N: usize> Sphere<N> {
+    pub fn new(center: Loc<N>, radius: f64) -> Self {
+        Sphere { center, radius }
+    }
+}
+
+impl<const N: usize> Shape<N> for Sphere<N> {
+    fn signed_distance(&self, loc: &Loc<N>) -> f64 {
+        loc.distance_to(&self.center) - self.radius
+    }
+
+    fn bounding_box(&self) -> (Loc<N>, Loc<N>) {
+        let extent = Loc::cartesian([self.radius; N]);
+        (self.center - extent, self.center + extent)
+    }
+}
+
+/// Stretched sphere reaching `radii` from `center` along each of its axes, which can be rotated
+/// Used for elongated blobs that a single radius can't describe, like raindrops or wide glows
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Ellipsoid<const N: usize> {
+    center: Loc<N>,
+    radii: [f64; N],
+    rotation: Rotation<N>,
+}
+
+impl<const N: usize> Ellipsoid<N> {
+    /// Creates an ellipsoid lined up with the axes, reaching `radii` from `center` along each
+    pub fn new(center: Loc<N>, radii: [f64; N]) -> Self {
+        Ellipsoid {
+            center,
+            radii: radii.map(f64::abs),
+            rotation: Rotation::identity(),
+        }
+    }
+
+    /// Returns this ellipsoid turned around its center by `rotation`, which should only rotate
+    pub fn with_rotation(mut self, rotation: Transform<N>) -> Self {
+        self.rotation = Rotation::new(rotation);
+        self
+    }
+
+    pub fn center(&self) -> Loc<N> {
+        self.center
+    }
+
+    pub fn radii(&self) -> [f64; N] {
+        self.radii
+    }
+
+    /// Returns how far out `loc` is as a fraction of the way to the surface in its direction,
+    /// so 0 at the center, 1 on the surface and above 1 outside
+    pub fn normalized_distance(&self, loc: &Loc<N>) -> f64 {
+        let local = self.rotation.unrotate(&(*loc - self.center));
+        local
+            .coords
+            .iter()
+            .zip(self.radii.iter())
+            .map(|(l, radius)| {
+                if *radius > 0.0 {
+                    (l / radius).powi(2)
+                } else if l.abs() < 1e-12 {
+                    0.0
+                } else {
+                    f64::INFINITY
+                }
+            })
+            .sum::<f64>()
+            .sqrt()
+    }
+}
+
+impl<const N: usize> Shape<N> for Ellipsoid<N> {
+    /// `normalized_distance` changes by at most 1 over the smallest radius per unit moved, so its
+    /// distance from 1 times the smallest radius never overshoots the true distance. Outside, the
+    /// distance past the sphere around the longest radius is a bound too, and the larger is kept
+    fn signed_distance(&self, loc: &Loc<N>) -> f64 {
+        let smallest = self.radii.iter().cloned().fold(f64::INFINITY, f64::min);
+        let largest = self.radii.iter().cloned().fold(0.0, f64::max);
+        let normalized = self.normalized_distance(loc);
+        if normalized <= 1.0 {
+            return (normalized - 1.0) * smallest;
+        }
+        // flat ellipsoids have no inside, so points off them are only known to be outside
+        let past_surface = if smallest > 0.0 {
+            (normalized - 1.0) * smallest
+        } else {
+            f64::MIN_POSITIVE
+        };
+        past_surface.max(loc.distance_to(&self.center) - largest)
+    }
+
+    fn bounding_box(&self) -> (Loc<N>, Loc<N>) {
+        let matrix = self.rotation.transform().matrix();
+        let mut extents = [0.0; N];
+        for (i, extent) in extents.iter_mut().enumerate() {
+            *extent = (0..N)
+                .map(|j| (matrix[i][j] * self.radii[j]).powi(2))
+                .sum::<f64>()
+                .sqrt();
+        }
+        let extents = Loc::cartesian(extents);
+        (self.center - extents, self.center + extents)
+    }
+}
+
+/// Box reaching `half_extents` from `center` along each of its axes, which can be rotated
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct OrientedBox<const N: usize> {
+    center: Loc<N>,
+    half_extents: [f64; N],
+    rotation: Rotation<N>,
+}
+
+impl<const N: usize> OrientedBox<N> {
+    /// Creates a box lined up with the axes, reaching `half_extents` from `center` along each
+    pub fn new(center: Loc<N>, half_extents: [f64; N]) -> Self {
+        OrientedBox {
+            center,
+            half_extents,
+            rotation: Rotation::identity(),
+        }
+    }
+
+    /// Creates the box with corners at `lower` and `upper`
+    pub fn from_corners(lower: Loc<N>, upper: Loc<N>) -> Self {
+        let half = (upper - lower) * 0.5;
+        OrientedBox::new(lower + half, half.coords.map(f64::abs))
+    }
+
+    /// Returns this box turned around its center by `rotation`, which should only rotate
+    pub fn with_rotation(mut self, rotation: Transform<N>) -> Self {
+        self.rotation = Rotation::new(rotation);
+        self
+    }
+}
+
+impl<const N: usize> Shape<N> for OrientedBox<N> {
+    fn signed_distance(&self, loc: &Loc<N>) -> f64 {
+        let local = self.rotation.unrotate(&(*loc - self.center));
+        let mut outside = 0.0;
+        let mut largest = f64::MIN;
+        for i in 0..N {
+            let q = local.coords[i].abs() - self.half_extents[i];
+            outside += q.max(0.0).powi(2);
+            largest = largest.max(q);
+        }
+        outside.sqrt() + largest.min(0.0)
+    }
+
+    fn bounding_box(&self) -> (Loc<N>, Loc<N>) {
+        let mut lower = self.half_extents;
+        lower.iter_mut().for_each(|h| *h = -*h);
+        let (lower, upper) = self
+            .rotation
+            .transform()
+            .bounding_box(&lower, &self.half_extents);
+        (
+            self.center + Loc::cartesian(lower),
+            self.center + Loc::cartesian(upper),
+        )
+    }
+}
+
+/// Every point within `radius` of the line segment between `start` and `end`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Capsule<const N: usize> {
+    start: Loc<N>,
+    end: Loc<N>,
+    radius: f64,
+}
+
+impl<const N: usize> Capsule<N> {
+    pub fn new(start: Loc<N>, end: Loc<N>, radius: f64) -> Self {
+        Capsule { start, end, radius }
+    }
+}
+
+impl<const N: usize> Shape<N> for Capsule<N> {
+    fn signed_distance(&self, loc: &Loc<N>) -> f64 {
+        let axis = self.end - self.start;
+        let length_squared = axis.dot(&axis);
+        let amount = if length_squared > 0.0 {
+            ((*loc - self.start).dot(&axis) / length_squared).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        loc.distance_to(&self.start.lerp(&self.end, amount)) - self.radius
+    }
+
+    fn bounding_box(&self) -> (Loc<N>, Loc<N>) {
+        let extent = Loc::cartesian([self.radius; N]);
+        (
+            self.start.min(&self.end) - extent,
+            self.start.max(&self.end) + extent,
+        )
+    }
+}
+
+/// Cylinder of `radius` around the line segment between `start` and `end`, with flat ends
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Cylinder<const N: usize> {
+    start: Loc<N>,
+    end: Loc<N>,
+    radius: f64,
+}
+
+impl<const N: usize> Cylinder<N> {
+    pub fn new(start: Loc<N>, end: Loc<N>, radius: f64) -> Self {
+        Cylinder { start, end, radius }
+    }
+}
+
+impl<const N: usize> Shape<N> for Cylinder<N> {
+    fn signed_distance(&self, loc: &Loc<N>) -> f64 {
+        let axis = self.end - self.start;
+        let height = axis.norm();
+        let offset = *loc - self.start;
+        if height == 0.0 {
+            return offset.norm().max(self.radius) - self.radius;
+        }
+        let along = offset.dot(&axis) / height;
+        let radial = (offset - axis * (along / height)).norm() - self.radius;
+        let axial = (along - height / 2.0).abs() - height / 2.0;
+        radial.max(axial).min(0.0) + radial.max(0.0).hypot(axial.max(0.0))
+    }
+
+    fn bounding_box(&self) -> (Loc<N>, Loc<N>) {
+        let extents = disk_extents(&(self.end - self.start), self.radius);
+        (
+            self.start.min(&self.end) - extents,
+            self.start.max(&self.end) + extents,
+        )
+    }
+}
+
+/// Ring of `minor_radius` thickness going around `center` at `major_radius`
+/// The ring lies in the plane of the first 2 axes unless it is rotated
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Torus<const N: usize> {
+    center: Loc<N>,
+    major_radius: f64,
+    minor_radius: f64,
+    rotation: Rotation<N>,
+}
+
+impl<const N: usize> Torus<N> {
+    pub fn new(center: Loc<N>, major_radius: f64, minor_radius: f64) -> Self {
+        assert!(N >= 2, "Torus needs at least 2 dimensions");
+        Torus {
+            center,
+            major_radius,
+            minor_radius,
+            rotation: Rotation::identity(),
+        }
+    }
+
+    /// Returns this torus turned around its center by `rotation`, which should only rotate
+    pub fn with_rotation(mut self, rotation: Transform<N>) -> Self {
+        self.rotation = Rotation::new(rotation);
+        self
+    }
+}
+
+impl<const N: usize> Shape<N> for Torus<N> {
+    fn signed_distance(&self, loc: &Loc<N>) -> f64 {
+        let local = self.rotation.unrotate(&(*loc - self.center));
+        let ring = local.coords[0].hypot(local.coords[1]) - self.major_radius;
+        let rest: f64 = local.coords[2..].iter().map(|c| c * c).sum();
+        (ring * ring + rest).sqrt() - self.minor_radius
+    }
+
+    fn bounding_box(&self) -> (Loc<N>, Loc<N>) {
+        let mut upper = [self.minor_radius; N];
+        upper[0] += self.major_radius;
+        upper[1] += self.major_radius;
+        let lower = upper.map(|e| -e);
+        let (lower, upper) = self.rotation.transform().bounding_box(&lower, &upper);
+        (
+            self.center + Loc::cartesian(lower),
+            self.center + Loc::cartesian(upper),
+        )
+    }
+}
+
+/// Cone with its point at `apex`, widening to `radius` at a flat base centered on `base`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Cone<const N: usize> {
+    apex: Loc<N>,
+    base: Loc<N>,
+    radius: f64,
+}
+
+impl<const N: usize> Cone<N> {
+    pub fn new(apex: Loc<N>, base: Loc<N>, radius: f64) -> Self {
+        Cone { apex, base, radius }
+    }
+}
+
+impl<const N: usize> Shape<N> for Cone<N> {
+    fn signed_distance(&self, loc: &Loc<N>) -> f64 {
+        // capped cone with radius 0 at the apex, measured in the plane through its axis
+        let axis = self.base - self.apex;
+        let axis_squared = axis.dot(&axis);
+        let offset = *loc - self.apex;
+        if axis_squared == 0.0 {
+            return offset.norm();
+        }
+        let along = offset.dot(&axis) / axis_squared;
+        let radial = (offset.dot(&offset) - along * along * axis_squared)
+            .max(0.0)
+            .sqrt();
+
+        let cap_radial = (radial - if along < 0.5 { 0.0 } else { self.radius }).max(0.0);
+        let cap_axial = (along - 0.5).abs() - 0.5;
+        let k = self.radius * self.radius + axis_squared;
+        let f = ((self.radius * radial + along * axis_squared) / k).clamp(0.0, 1.0);
+        let side_radial = radial - f * self.radius;
+        let side_axial = along - f;
+        let sign = if side_radial < 0.0 && cap_axial < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+        sign * (cap_radial * cap_radial + cap_axial * cap_axial * axis_squared)
+            .min(side_radial * side_radial + side_axial * side_axial * axis_squared)
+            .sqrt()
+    }
+
+    fn bounding_box(&self) -> (Loc<N>, Loc<N>) {
+        let extents = disk_extents(&(self.base - self.apex), self.radius);
+        (
+            self.apex.min(&(self.base - extents)),
+            self.apex.max(&(self.base + extents)),
+        )
+    }
+}
+
+/// Everything on one side of a flat plane, going on forever
+/// Points where `normal · point <= offset` are inside, so `normal` points out of the shape
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Plane<const N: usize> {
+    normal: Loc<N>,
+    offset: f64,
+}
+
+impl<const N: usize> Plane<N> {
+    /// Creates the plane of points where `normal · point = offset`, with everything on the other
+    /// side from where `normal` points inside. `normal` does not need to have length 1
+    pub fn new(normal: Loc<N>, offset: f64) -> Self {
+        let length = normal.norm();
+        Plane {
+            normal: normal.normalize(),
+            offset: if length > 0.0 {
+                offset / length
+            } else {
+                offset
+            },
+        }
+    }
+
+    /// Creates the plane through `point` facing `normal`
+    pub fn through(point: &Loc<N>, normal: Loc<N>) -> Self {
+        Plane::new(normal, normal.dot(point))
+    }
+
+    /// Direction out of the shape, with length 1
+    pub fn normal(&self) -> Loc<N> {
+        self.normal
+    }
+
+    /// Distance of the plane from the origin along `normal`
+    pub fn offset(&self) -> f64 {
+        self.offset
+    }
+}
+
+impl<const N: usize> Shape<N> for Plane<N> {
+    fn signed_distance(&self, loc: &Loc<N>) -> f64 {
+        self.normal.dot(loc) - self.offset
+    }
+
+    fn bounding_box(&self) -> (Loc<N>, Loc<N>) {
+        let mut lower = [f64::NEG_INFINITY; N];
+        let mut upper = [f64::INFINITY; N];
+        // only a plane facing straight along an axis cuts the box off
+        let facing: Vec<usize> = (0..N).filter(|i| self.normal.coords[*i] != 0.0).collect();
+        if let [axis] = facing[..] {
+            let edge = self.offset / self.normal.coords[axis];
+            if self.normal.coords[axis] > 0.0 {
+                upper[axis] = edge;
+            } else {
+                lower[axis] = edge;
+            }
+        }
+        (Loc::cartesian(lower), Loc::cartesian(upper))
+    }
+}
+
+/// Rotation of a shape around its center
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Rotation<const N: usize> {
+    transform: Transform<N>,
+}
+
+impl<const N: usize> Rotation<N> {
+    fn identity() -> Self {
+        Rotation {
+            transform: Transform::identity(),
+        }
+    }
+
+    /// Keeps only the matrix of `transform`, since shapes rotate around their own center
+    fn new(transform: Transform<N>) -> Self {
+        Rotation {
+            transform: Transform::from_matrix(transform.matrix(), [0.0; N]),
+        }
+    }
+
+    fn transform(&self) -> &Transform<N> {
+        &self.transform
+    }
+
+    /// Turns `offset` from the shape's center back into the shape's own axes
+    /// The inverse of a rotation is its transpose
+    fn unrotate(&self, offset: &Loc<N>) -> Loc<N> {
+        let matrix = self.transform.matrix();
+        let mut local = [0.0; N];
+        for (i, l) in local.iter_mut().enumerate() {
+            *l = (0..N).map(|j| matrix[j][i] * offset.coords[j]).sum();
+        }
+        Loc::cartesian(local)
+    }
+}
+
+/// Returns how far a disk of `radius` facing along `axis` reaches along each axis
+fn disk_extents<const N: usize>(axis: &Loc<N>, radius: f64) -> Loc<N> {
+    let direction = axis.normalize();
+    let length = direction.norm();
+    Loc::cartesian(direction.coords.map(|d| {
+        if length == 0.0 {
+            radius
+        } else {
+            radius * (1.0 - d * d).max(0.0).sqrt()
+        }
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    fn approx(a: f64, b: f64) -> bool {
+        (a - b).abs() < 0.0001
+    }
+
+    fn approx_loc<const N: usize>(loc: &Loc<N>, coords: [f64; N]) -> bool {
+        loc.distance_to(&Loc::cartesian(coords)) < 0.0001
+    }
+
+    #[test]
+    fn sphere() {
+        let sphere = Sphere::new(Loc::cartesian([0.5, 0.5, 0.5]), 0.25);
+        assert!(approx(
+            sphere.signed_distance(&Loc::cartesian([0.5, 0.5, 0.5])),
+            -0.25
+        ));
+        assert!(approx(
+            sphere.signed_distance(&Loc::cartesian([1.0, 0.5, 0.5])),
+            0.25
+        ));
+        assert!(sphere.contains(&Loc::cartesian([0.5, 0.7, 0.5])));
+        assert!(!sphere.contains(&Loc::cartesian([0.7, 0.7, 0.5])));
+        let (lower, upper) = sphere.bounding_box();
+        assert!(approx_loc(&lower, [0.25, 0.25, 0.25]));
+        assert!(approx_loc(&upper, [0.75, 0.75, 0.75]));
+    }
+
+    #[test]
+    fn ellipsoid() {
+        let ellipsoid = Ellipsoid::new(Loc::cartesian([0.5, 0.5, 0.5]), [0.4, 0.1, 0.2]);
+        let normalized = |coords| ellipsoid.normalized_distance(&Loc::cartesian(coords));
+        assert!(approx(normalized([0.5, 0.5, 0.5]), 0.0));
+        assert!(approx(normalized([0.9, 0.5, 0.5]), 1.0));
+        assert!(approx(normalized([0.5, 0.55, 0.5]), 0.5));
+        assert!(approx(normalized([0.5, 0.5, 0.1]), 2.0));
+        assert!(approx(normalized([0.7, 0.55, 0.5]), 0.5_f64.sqrt()));
+
+        // the surface is at 0, and distances never overshoot the true ones
+        assert!(approx(
+            ellipsoid.signed_distance(&Loc::cartesian([0.9, 0.5, 0.5])),
+            0.0
+        ));
+        assert!(approx(
+            ellipsoid.signed_distance(&Loc::cartesian([0.5, 0.5, 0.5])),
+            -0.1
+        ));
+        let far = ellipsoid.signed_distance(&Loc::cartesian([1.0, 0.5, 0.5]));
+        assert!(far > 0.0 && far <= 0.1 + 1e-12);
+        let above = ellipsoid.signed_distance(&Loc::cartesian([0.5, 0.9, 0.5]));
+        assert!(above > 0.0 && above <= 0.3 + 1e-12);
+        assert!(ellipsoid.contains(&Loc::cartesian([0.8, 0.5, 0.55])));
+        assert!(!ellipsoid.contains(&Loc::cartesian([0.8, 0.58, 0.5])));
+        let (lower, upper) = ellipsoid.bounding_box();
+        assert!(approx_loc(&lower, [0.1, 0.4, 0.3]));
+        assert!(approx_loc(&upper, [0.9, 0.6, 0.7]));
+
+        // flat along an axis with no radius
+        let disk = Ellipsoid::new(Loc::cartesian([0.5, 0.5]), [0.2, 0.0]);
+        assert!(approx(
+            disk.normalized_distance(&Loc::cartesian([0.6, 0.5])),
+            0.5
+        ));
+        assert_eq!(
+            disk.normalized_distance(&Loc::cartesian([0.6, 0.51])),
+            f64::INFINITY
+        );
+        assert!(disk.contains(&Loc::cartesian([0.6, 0.5])));
+        assert!(!disk.contains(&Loc::cartesian([0.6, 0.51])));
+        assert!(disk.signed_distance(&Loc::cartesian([1.0, 0.5])) > 0.29);
+
+        // and it combines with other shapes
+        let with_ring = ellipsoid.difference(Sphere::new(Loc::cartesian([0.5, 0.5, 0.5]), 0.05));
+        assert!(!with_ring.contains(&Loc::cartesian([0.5, 0.5, 0.5])));
+        assert!(with_ring.contains(&Loc::cartesian([0.7, 0.5, 0.5])));
+    }
+
+    #[test]
+    fn rotated_ellipsoid() {
+        // long side turned from along x to along y
+        let ellipsoid = Ellipsoid::new(Loc::cartesian([0.5, 0.5]), [0.4, 0.1])
+            .with_rotation(Transform::rotation(0, 1, PI / 2.0));
+        let normalized = |ellipsoid: &Ellipsoid<2>, coords| {
+            ellipsoid.normalized_distance(&Loc::cartesian(coords))
+        };
+        assert!(approx(normalized(&ellipsoid, [0.5, 0.8]), 0.75));
+        assert!(approx(normalized(&ellipsoid, [0.6, 0.5]), 1.0));
+        let (lower, upper) = ellipsoid.bounding_box();
+        assert!(approx_loc(&lower, [0.4, 0.1]));
+        assert!(approx_loc(&upper, [0.6, 0.9]));
+
+        // a quarter turn the other way is the same ellipsoid
+        let other_way = Ellipsoid::new(Loc::cartesian([0.5, 0.5]), [0.4, 0.1])
+            .with_rotation(Transform::rotation(0, 1, -PI / 2.0));
+        assert!(approx(normalized(&other_way, [0.5, 0.8]), 0.75));
+
+        // diagonal
+        let diagonal = Ellipsoid::new(Loc::cartesian([0.5, 0.5]), [0.4, 0.1])
+            .with_rotation(Transform::rotation(0, 1, PI / 4.0));
+        let step = 0.3 / 2.0_f64.sqrt();
+        assert!(approx(
+            normalized(&diagonal, [0.5 + step, 0.5 + step]),
+            0.75
+        ));
+        assert!(normalized(&diagonal, [0.5 + step, 0.5 - step]) > 1.0);
+        let (lower, upper) = diagonal.bounding_box();
+        let extent = ((0.16 + 0.01) / 2.0_f64).sqrt();
+        assert!(approx(lower.coords[0], 0.5 - extent) && approx(upper.coords[1], 0.5 + extent));
+    }
+
+    #[test]
+    fn oriented_box() {
+        let cube = OrientedBox::new(Loc::cartesian([0.5, 0.5]), [0.2, 0.1]);
+        assert!(approx(
+            cube.signed_distance(&Loc::cartesian([0.5, 0.5])),
+            -0.1
+        ));
+        assert!(approx(
+            cube.signed_distance(&Loc::cartesian([0.9, 0.5])),
+            0.2
+        ));
+        assert!(approx(
+            cube.signed_distance(&Loc::cartesian([1.0, 1.0])),
+            0.3_f64.hypot(0.4)
+        ));
+        let from_corners =
+            OrientedBox::from_corners(Loc::cartesian([0.7, 0.6]), Loc::cartesian([0.3, 0.4]));
+        for loc in [[0.5, 0.5], [0.9, 0.5], [1.0, 1.0], [0.6, 0.55]] {
+            let loc = Loc::cartesian(loc);
+            assert!(approx(
+                from_corners.signed_distance(&loc),
+                cube.signed_distance(&loc)
+            ));
+        }
+
+        // a quarter turn swaps which axis is long
+        let turned = cube.with_rotation(Transform::rotation(0, 1, PI / 2.0));
+        assert!(approx(
+            turned.signed_distance(&Loc::cartesian([0.5, 0.7])),
+            0.0
+        ));
+        assert!(approx(
+            turned.signed_distance(&Loc::cartesian([0.7, 0.5])),
+            0.1
+        ));
+        let (lower, upper) = turned.bounding_box();
+        assert!(approx_loc(&lower, [0.4, 0.3]));
+        assert!(approx_loc(&upper, [0.6, 0.7]));
+
+        // an eighth turn of a square reaches out to its corners
+        let diamond = OrientedBox::new(Loc::cartesian([0.5, 0.5]), [0.1, 0.1])
+            .with_rotation(Transform::rotation(0, 1, PI / 4.0));
+        let corner = 0.1 * 2.0_f64.sqrt();
+        assert!(approx(
+            diamond.signed_distance(&Loc::cartesian([0.5 + corner, 0.5])),
+            0.0
+        ));
+        assert!(approx_loc(
+            &diamond.bounding_box().1,
+            [0.5 + corner, 0.5 + corner]
+        ));
+    }
+
+    #[test]
+    fn capsule_and_cylinder() {
+        let (start, end) = (
+            Loc::cartesian([0.2, 0.5, 0.5]),
+            Loc::cartesian([0.8, 0.5, 0.5]),
+        );
+        let capsule = Capsule::new(start, end, 0.1);
+        assert!(approx(
+            capsule.signed_distance(&Loc::cartesian([0.5, 0.5, 0.5])),
+            -0.1
+        ));
+        assert!(approx(
+            capsule.signed_distance(&Loc::cartesian([0.5, 0.8, 0.5])),
+            0.2
+        ));
+        assert!(approx(
+            capsule.signed_distance(&Loc::cartesian([1.0, 0.5, 0.5])),
+            0.1
+        ));
+        let (lower, upper) = capsule.bounding_box();
+        assert!(approx_loc(&lower, [0.1, 0.4, 0.4]));
+        assert!(approx_loc(&upper, [0.9, 0.6, 0.6]));
+
+        let cylinder = Cylinder::new(start, end, 0.1);
+        assert!(approx(
+            cylinder.signed_distance(&Loc::cartesian([0.5, 0.5, 0.5])),
+            -0.1
+        ));
+        assert!(approx(
+            cylinder.signed_distance(&Loc::cartesian([0.5, 0.8, 0.5])),
+            0.2
+        ));
+        // flat ends, unlike the capsule
+        assert!(approx(
+            cylinder.signed_distance(&Loc::cartesian([1.0, 0.5, 0.5])),
+            0.2
+        ));
+        assert!(approx(
+            cylinder.signed_distance(&Loc::cartesian([0.9, 0.7, 0.5])),
+            0.1_f64.hypot(0.1)
+        ));
+        assert!(approx(
+            cylinder.signed_distance(&Loc::cartesian([0.21, 0.5, 0.5])),
+            -0.01
+        ));
+        let (lower, upper) = cylinder.bounding_box();
+        assert!(approx_loc(&lower, [0.2, 0.4, 0.4]));
+        assert!(approx_loc(&upper, [0.8, 0.6, 0.6]));
+    }
+
+    #[test]
+    fn torus() {
+        let torus = Torus::new(Loc::cartesian([0.5, 0.5, 0.5]), 0.3, 0.1);
+        assert!(approx(
+            torus.signed_distance(&Loc::cartesian([0.8, 0.5, 0.5])),
+            -0.1
+        ));
+        assert!(approx(
+            torus.signed_distance(&Loc::cartesian([0.5, 0.5, 0.5])),
+            0.2
+        ));
+        assert!(approx(
+            torus.signed_distance(&Loc::cartesian([0.5, 0.8, 0.7])),
+            0.1
+        ));
+        let (lower, upper) = torus.bounding_box();
+        assert!(approx_loc(&lower, [0.1, 0.1, 0.4]));
+        assert!(approx_loc(&upper, [0.9, 0.9, 0.6]));
+
+        // standing up in the x-z plane
+        let standing = torus.with_rotation(Transform::rotation(1, 2, PI / 2.0));
+        assert!(approx(
+            standing.signed_distance(&Loc::cartesian([0.5, 0.5, 0.8])),
+            -0.1
+        ));
+        assert!(approx(
+            standing.signed_distance(&Loc::cartesian([0.5, 0.8, 0.5])),
+            0.3_f64.hypot(0.3) - 0.1
+        ));
+        let (lower, upper) = standing.bounding_box();
+        assert!(approx_loc(&lower, [0.1, 0.4, 0.1]));
+        assert!(approx_loc(&upper, [0.9, 0.6, 0.9]));
+    }
+
+    #[test]
+    fn cone() {
+        // point up at y = 0.9, base of radius 0.3 at y = 0.3
+        let cone = Cone::new(Loc::cartesian([0.5, 0.9]), Loc::cartesian([0.5, 0.3]), 0.3);
+        assert!(cone.contains(&Loc::cartesian([0.5, 0.5])));
+        assert!(cone.contains(&Loc::cartesian([0.7, 0.35])));
+        assert!(!cone.contains(&Loc::cartesian([0.7, 0.8])));
+        assert!(approx(
+            cone.signed_distance(&Loc::cartesian([0.5, 1.0])),
+            0.1
+        ));
+        assert!(approx(
+            cone.signed_distance(&Loc::cartesian([0.5, 0.1])),
+            0.2
+        ));
+        assert!(approx(
+            cone.signed_distance(&Loc::cartesian([0.5, 0.35])),
+            -0.05
+        ));
+        // perpendicular distance to the side
+        let side = cone.signed_distance(&Loc::cartesian([0.8, 0.9]));
+        assert!(approx(side, 0.3 * (2.0 / 5.0_f64.sqrt())));
+        let (lower, upper) = cone.bounding_box();
+        assert!(approx_loc(&lower, [0.2, 0.3]));
+        assert!(approx_loc(&upper, [0.8, 0.9]));
+    }
+
+    #[test]
+    fn plane() {
+        let floor = Plane::new(Loc::cartesian([0.0, 0.0, 2.0]), 0.5);
+        assert!(approx(
+            floor.signed_distance(&Loc::cartesian([0.5, 0.5, 0.25])),
+            0.0
+        ));
+        assert!(approx(
+            floor.signed_distance(&Loc::cartesian([0.5, 0.5, 0.0])),
+            -0.25
+        ));
+        assert!(approx(
+            floor.signed_distance(&Loc::cartesian([0.1, 0.9, 1.0])),
+            0.75
+        ));
+        let (lower, upper) = floor.bounding_box();
+        assert_eq!(lower.coords, [f64::NEG_INFINITY; 3]);
+        assert_eq!(upper.coords, [f64::INFINITY, f64::INFINITY, 0.25]);
+
+        let slanted = Plane::through(&Loc::cartesian([0.5, 0.5]), Loc::cartesian([1.0, 1.0]));
+        assert!(approx(
+            slanted.signed_distance(&Loc::cartesian([0.5, 0.5])),
+            0.0
+        ));
+        assert!(approx(
+            slanted.signed_distance(&Loc::cartesian([0.0, 0.0])),
+            -(0.5_f64.sqrt())
+        ));
+        assert_eq!(slanted.bounding_box().1.coords, [f64::INFINITY; 2]);
+    }
+}
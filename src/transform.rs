@@ -0,0 +1,333 @@
+use crate::loc::Loc;
+
+/// Affine transformation of N-dimensional space: any combination of translations, scales and
+/// rotations. Applying it to a point multiplies the point by a matrix, then adds a translation
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Transform<const N: usize> {
+    matrix: [[f64; N]; N],
+    translation: [f64; N],
+}
+
+impl<const N: usize> Transform<N> {
+    /// Creates a transform that leaves every point where it is
+    pub fn identity() -> Self {
+        let mut matrix = [[0.0; N]; N];
+        for (i, row) in matrix.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+        Transform {
+            matrix,
+            translation: [0.0; N],
+        }
+    }
+
+    /// Moves every point by `offset`
+    pub fn translation(offset: [f64; N]) -> Self {
+        Transform {
+            translation: offset,
+            ..Transform::identity()
+        }
+    }
+
+    /// Scales every point away from the origin by `factor`
+    pub fn scale(factor: f64) -> Self {
+        Transform::scale_axes([factor; N])
+    }
+
+    /// Scales every point away from the origin by a separate factor along each axis
+    pub fn scale_axes(factors: [f64; N]) -> Self {
+        let mut transform = Transform::identity();
+        for (i, factor) in factors.iter().enumerate() {
+            transform.matrix[i][i] = *factor;
+        }
+        transform
+    }
+
+    /// Rotates every point around the origin by `angle` radians in the plane of axes `from` and
+    /// `to`, turning the `from` axis toward the `to` axis. Every other axis is left alone
+    /// In 2 dimensions `rotation(0, 1, angle)` is a counterclockwise rotation, and in 3
+    /// `rotation(0, 1, angle)` rotates around the z axis
+    ///
+    /// Panics if `from` and `to` are the same axis or either is not less than N
+    pub fn rotation(from: usize, to: usize, angle: f64) -> Self {
+        assert!(from < N && to < N, "Rotation axes must be less than {}", N);
+        assert!(from != to, "Rotation needs two different axes");
+        let (sin, cos) = angle.sin_cos();
+        let mut transform = Transform::identity();
+        transform.matrix[from][from] = cos;
+        transform.matrix[to][to] = cos;
+        transform.matrix[to][from] = sin;
+        transform.matrix[from][to] = -sin;
+        transform
+    }
+
+    /// Creates a transform from a matrix, where each row gives the weights of the input
+    /// coordinates for that output coordinate, followed by a translation
+    pub fn from_matrix(matrix: [[f64; N]; N], translation: [f64; N]) -> Self {
+        Transform {
+            matrix,
+            translation,
+        }
+    }
+
+    /// Returns the transform that applies this one and then `next`
+    pub fn then(&self, next: &Transform<N>) -> Self {
+        let mut matrix = [[0.0; N]; N];
+        for (i, row) in matrix.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..N).map(|k| next.matrix[i][k] * self.matrix[k][j]).sum();
+            }
+        }
+        Transform {
+            matrix,
+            translation: next.apply_point(&self.translation),
+        }
+    }
+
+    /// Returns this transform done around `center` instead of the origin, such as rotating or
+    /// scaling around the middle of the arrangement at [0.5; N]
+    pub fn about(&self, center: &[f64; N]) -> Self {
+        let mut to_origin = [0.0; N];
+        for (i, c) in to_origin.iter_mut().enumerate() {
+            *c = -center[i];
+        }
+        Transform::translation(to_origin)
+            .then(self)
+            .then(&Transform::translation(*center))
+    }
+
+    /// Returns the transform that undoes this one, or `None` if this one flattens space, such
+    /// as scaling by 0
+    pub fn inverse(&self) -> Option<Self> {
+        // Gauss-Jordan elimination with partial pivoting
+        let mut m = self.matrix;
+        let mut inverse = Transform::<N>::identity().matrix;
+        for col in 0..N {
+            let pivot = (col..N).max_by(|a, b| m[*a][col].abs().total_cmp(&m[*b][col].abs()))?;
+            if m[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            m.swap(col, pivot);
+            inverse.swap(col, pivot);
+
+            let scale = m[col][col];
+            for j in 0..N {
+                m[col][j] /= scale;
+                inverse[col][j] /= scale;
+            }
+            for row in 0..N {
+                if row != col {
+                    let factor = m[row][col];
+                    for j in 0..N {
+                        m[row][j] -= factor * m[col][j];
+                        inverse[row][j] -= factor * inverse[col][j];
+                    }
+                }
+            }
+        }
+
+        let mut translation = [0.0; N];
+        for (i, t) in translation.iter_mut().enumerate() {
+            *t = -(0..N)
+                .map(|j| inverse[i][j] * self.translation[j])
+                .sum::<f64>();
+        }
+        Some(Transform {
+            matrix: inverse,
+            translation,
+        })
+    }
+
+    pub fn matrix(&self) -> [[f64; N]; N] {
+        self.matrix
+    }
+
+    /// The translation added after multiplying by the matrix
+    pub fn offset(&self) -> [f64; N] {
+        self.translation
+    }
+
+    /// Returns `loc` moved by this transform
+    pub fn apply(&self, loc: &Loc<N>) -> Loc<N> {
+        Loc::cartesian(self.apply_point(&loc.coords))
+    }
+
+    #[inline]
+    pub(crate) fn apply_point(&self, point: &[f64; N]) -> [f64; N] {
+        let mut out = self.translation;
+        for (i, o) in out.iter_mut().enumerate() {
+            for (j, p) in point.iter().enumerate() {
+                *o += self.matrix[i][j] * p;
+            }
+        }
+        out
+    }
+
+    /// Returns the corners of the smallest axis aligned box holding the box between `lower` and
+    /// `upper` after it is transformed
+    pub(crate) fn bounding_box(&self, lower: &[f64; N], upper: &[f64; N]) -> ([f64; N], [f64; N]) {
+        let mut center = [0.0; N];
+        let mut half = [0.0; N];
+        for i in 0..N {
+            center[i] = (lower[i] + upper[i]) / 2.0;
+            half[i] = (upper[i] - lower[i]).abs() / 2.0;
+        }
+        let center = self.apply_point(&center);
+        let mut extents = [0.0; N];
+        for (i, extent) in extents.iter_mut().enumerate() {
+            *extent = (0..N).map(|j| self.matrix[i][j].abs() * half[j]).sum();
+        }
+        corners(&center, &extents)
+    }
+
+    /// Returns the corners of the smallest axis aligned box holding the ball of `radius` around
+    /// `center` after it is transformed
+    pub(crate) fn bounding_box_of_ball(
+        &self,
+        center: &[f64; N],
+        radius: f64,
+    ) -> ([f64; N], [f64; N]) {
+        let center = self.apply_point(center);
+        let mut extents = [0.0; N];
+        for (i, extent) in extents.iter_mut().enumerate() {
+            let row_length: f64 = self.matrix[i].iter().map(|v| v * v).sum::<f64>().sqrt();
+            *extent = radius.abs() * row_length;
+        }
+        corners(&center, &extents)
+    }
+}
+
+impl<const N: usize> Default for Transform<N> {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}
+
+fn corners<const N: usize>(center: &[f64; N], extents: &[f64; N]) -> ([f64; N], [f64; N]) {
+    let mut lower = *center;
+    let mut upper = *center;
+    for i in 0..N {
+        lower[i] -= extents[i];
+        upper[i] += extents[i];
+    }
+    (lower, upper)
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    fn approx<const N: usize>(p1: &[f64; N], p2: &[f64; N]) -> bool {
+        p1.iter()
+            .zip(p2.iter())
+            .all(|(a, b)| (a - b).abs() < 0.0001)
+    }
+
+    #[test]
+    fn translate_and_scale() {
+        let loc = Loc::cartesian([0.1, 0.2, 0.3]);
+        let moved = Transform::translation([0.5, 0.0, -0.1]).apply(&loc);
+        assert!(approx(&moved.coords, &[0.6, 0.2, 0.2]));
+
+        let scaled = Transform::scale(2.0).apply(&loc);
+        assert!(approx(&scaled.coords, &[0.2, 0.4, 0.6]));
+
+        let scaled = Transform::scale_axes([1.0, 0.5, 0.0]).apply(&loc);
+        assert!(approx(&scaled.coords, &[0.1, 0.1, 0.0]));
+
+        let identity = Transform::identity().apply(&loc);
+        assert_eq!(identity.coords, loc.coords);
+    }
+
+    #[test]
+    fn rotate() {
+        let rotation = Transform::rotation(0, 1, PI / 2.0);
+        assert!(approx(
+            &rotation.apply(&Loc::cartesian([1.0, 0.0])).coords,
+            &[0.0, 1.0]
+        ));
+        assert!(approx(
+            &rotation.apply(&Loc::cartesian([0.0, 1.0])).coords,
+            &[-1.0, 0.0]
+        ));
+
+        // rotating in the x-z plane leaves y and w alone
+        let rotation = Transform::rotation(0, 2, PI / 2.0);
+        assert!(approx(
+            &rotation.apply(&Loc::cartesian([1.0, 0.3, 0.0, 0.7])).coords,
+            &[0.0, 0.3, 1.0, 0.7]
+        ));
+
+        // around the middle of the arrangement
+        let rotation = Transform::rotation(0, 1, PI).about(&[0.5, 0.5]);
+        assert!(approx(
+            &rotation.apply(&Loc::cartesian([0.2, 0.4])).coords,
+            &[0.8, 0.6]
+        ));
+    }
+
+    #[test]
+    fn compose() {
+        let transform = Transform::scale(2.0).then(&Transform::translation([1.0, 0.0]));
+        assert!(approx(
+            &transform.apply(&Loc::cartesian([0.5, 0.5])).coords,
+            &[2.0, 1.0]
+        ));
+
+        let transform = Transform::translation([1.0, 0.0]).then(&Transform::scale(2.0));
+        assert!(approx(
+            &transform.apply(&Loc::cartesian([0.5, 0.5])).coords,
+            &[3.0, 1.0]
+        ));
+
+        let quarter = Transform::rotation(0, 1, PI / 4.0);
+        let half = quarter.then(&quarter);
+        assert!(approx(
+            &half.matrix()[0],
+            &Transform::<2>::rotation(0, 1, PI / 2.0).matrix()[0]
+        ));
+    }
+
+    #[test]
+    fn inverse() {
+        let transform = Transform::rotation(1, 2, 0.7)
+            .then(&Transform::scale_axes([2.0, 0.5, 3.0]))
+            .then(&Transform::translation([0.1, -0.4, 0.9]));
+        let inverse = transform.inverse().unwrap();
+        let loc = Loc::cartesian([0.3, 0.6, 0.9]);
+        assert!(approx(
+            &inverse.apply(&transform.apply(&loc)).coords,
+            &loc.coords
+        ));
+        assert!(approx(
+            &transform.apply(&inverse.apply(&loc)).coords,
+            &loc.coords
+        ));
+
+        assert!(Transform::<3>::scale_axes([1.0, 0.0, 1.0])
+            .inverse()
+            .is_none());
+    }
+
+    #[test]
+    fn bounding_boxes() {
+        let rotation = Transform::rotation(0, 1, PI / 4.0);
+        let (lower, upper) = rotation.bounding_box(&[-1.0, -1.0], &[1.0, 1.0]);
+        let diagonal = 2.0_f64.sqrt();
+        assert!(approx(&lower, &[-diagonal, -diagonal]));
+        assert!(approx(&upper, &[diagonal, diagonal]));
+
+        let (lower, upper) = rotation
+            .then(&Transform::translation([0.5, 0.5]))
+            .bounding_box_of_ball(&[0.0, 0.0], 0.2);
+        assert!(approx(&lower, &[0.3, 0.3]));
+        assert!(approx(&upper, &[0.7, 0.7]));
+
+        let (lower, upper) =
+            Transform::scale_axes([2.0, 1.0]).bounding_box_of_ball(&[0.0, 0.0], 0.2);
+        assert!(approx(&lower, &[-0.4, -0.2]));
+        assert!(approx(&upper, &[0.4, 0.2]));
+    }
+}